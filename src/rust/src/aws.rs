@@ -0,0 +1,116 @@
+use std::env;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use ini::Ini;
+
+/// Settings for one named profile in the shared AWS config and credentials
+/// files.
+#[derive(Debug, Default)]
+pub(crate) struct AwsProfile {
+    pub region: Option<String>,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub session_token: Option<String>,
+    pub role_arn: Option<String>,
+    pub external_id: Option<String>,
+    pub role_session_name: Option<String>,
+}
+
+/// Reads `profile` from `~/.aws/config` and `~/.aws/credentials`, honouring
+/// `AWS_CONFIG_FILE` and `AWS_SHARED_CREDENTIALS_FILE`.
+///
+/// A profile that assumes a role through `source_profile` takes its keys
+/// from the source profile, so the role can be assumed with them. A role
+/// with `credential_source` instead is assumed with the default chain's
+/// keys.
+///
+/// Only static keys and roles are understood. Profiles that sign in through
+/// SSO, `credential_process` or `web_identity_token_file` are refused, as is
+/// a profile with neither keys nor a role, rather than quietly falling back
+/// to the default chain and so to some other identity.
+pub(crate) fn load_profile(profile: &str) -> Result<AwsProfile> {
+    let config = load_ini("AWS_CONFIG_FILE", ".aws/config")?;
+    let credentials = load_ini("AWS_SHARED_CREDENTIALS_FILE", ".aws/credentials")?;
+
+    let config_section = match profile {
+        "default" => "default".to_string(),
+        name => format!("profile {name}"),
+    };
+    let from_config = config.as_ref().and_then(|c| c.section(Some(config_section)));
+    let from_credentials = credentials.as_ref().and_then(|c| c.section(Some(profile)));
+
+    if from_config.is_none() && from_credentials.is_none() {
+        anyhow::bail!("AWS profile `{profile}` was not found in the shared config files");
+    }
+
+    let get = |key: &str| {
+        from_credentials
+            .and_then(|props| props.get(key))
+            .or_else(|| from_config.and_then(|props| props.get(key)))
+            .map(str::to_string)
+    };
+
+    let has_keys = get("aws_access_key_id").is_some();
+    if !has_keys {
+        let unsupported = [
+            "sso_session",
+            "sso_start_url",
+            "sso_account_id",
+            "credential_process",
+            "web_identity_token_file",
+        ];
+        if let Some(key) = unsupported.iter().find(|key| get(key).is_some()) {
+            anyhow::bail!(
+                "AWS profile `{profile}` uses `{key}`, which isn't supported; \
+                 pass keys or run `aws configure export-credentials` first"
+            );
+        }
+        if get("role_arn").is_none() {
+            anyhow::bail!("AWS profile `{profile}` has neither access keys nor a `role_arn`");
+        }
+    }
+
+    let mut settings = AwsProfile {
+        region: from_config.and_then(|props| props.get("region")).map(str::to_string),
+        access_key_id: get("aws_access_key_id"),
+        secret_access_key: get("aws_secret_access_key"),
+        session_token: get("aws_session_token"),
+        role_arn: get("role_arn"),
+        external_id: get("external_id"),
+        role_session_name: get("role_session_name"),
+    };
+
+    if let Some(name) = get("source_profile") {
+        if name != profile {
+            let source = load_profile(&name)?;
+            if source.access_key_id.is_none() {
+                anyhow::bail!(
+                    "AWS profile `{profile}` assumes its role with `{name}`, which has no access keys"
+                );
+            }
+            settings.access_key_id = source.access_key_id;
+            settings.secret_access_key = source.secret_access_key;
+            settings.session_token = source.session_token;
+            settings.region = settings.region.or(source.region);
+        }
+    }
+
+    Ok(settings)
+}
+
+fn load_ini(var: &str, default: &str) -> Result<Option<Ini>> {
+    let path = match env::var_os(var) {
+        Some(path) => PathBuf::from(path),
+        None => match home::home_dir() {
+            Some(home) => home.join(default),
+            None => return Ok(None),
+        },
+    };
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    Ok(Some(Ini::load_from_file(path)?))
+}
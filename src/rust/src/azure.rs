@@ -0,0 +1,69 @@
+use std::env;
+use std::str::FromStr;
+
+use anyhow::Result;
+
+/// How an Azure constructor authenticates, selected by its `auth` argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AzureAuth {
+    /// Explicit key or SAS token if given, then a service principal from
+    /// the environment, then managed identity.
+    Auto,
+    /// Shared account key.
+    Key,
+    /// Shared access signature.
+    Sas,
+    /// Service principal from `AZURE_TENANT_ID`, `AZURE_CLIENT_ID` and
+    /// `AZURE_CLIENT_SECRET`.
+    Env,
+    /// Managed identity through the instance metadata service.
+    ManagedIdentity,
+}
+
+impl FromStr for AzureAuth {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(AzureAuth::Auto),
+            "key" => Ok(AzureAuth::Key),
+            "sas" => Ok(AzureAuth::Sas),
+            "env" => Ok(AzureAuth::Env),
+            "managed_identity" => Ok(AzureAuth::ManagedIdentity),
+            // opendal's Azure services sign every request themselves and
+            // have no way to take a bearer token from `az account
+            // get-access-token`.
+            "cli" => anyhow::bail!(
+                "`auth = \"cli\"` isn't supported: opendal can't use an Azure CLI \
+                 token; use `auth = \"env\"` with a service principal instead"
+            ),
+            other => anyhow::bail!(
+                "unknown Azure auth `{other}`; expected one of \
+                 auto, key, sas, env, managed_identity"
+            ),
+        }
+    }
+}
+
+/// A service principal read from the standard `AZURE_*` variables.
+#[derive(Debug)]
+pub(crate) struct ServicePrincipal {
+    pub tenant_id: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub authority_host: Option<String>,
+}
+
+impl ServicePrincipal {
+    /// Returns `None` unless tenant, client id and secret are all set.
+    pub(crate) fn from_env() -> Option<Self> {
+        let var = |name| env::var(name).ok().filter(|v: &String| !v.is_empty());
+
+        Some(ServicePrincipal {
+            tenant_id: var("AZURE_TENANT_ID")?,
+            client_id: var("AZURE_CLIENT_ID")?,
+            client_secret: var("AZURE_CLIENT_SECRET")?,
+            authority_host: var("AZURE_AUTHORITY_HOST"),
+        })
+    }
+}
@@ -0,0 +1,570 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use opendal::raw::*;
+use opendal::{Buffer, Metadata, Result};
+
+/// Where a [`CacheLayer`] keeps object contents. Stores are best effort:
+/// failing to read or write an entry just means going back to the service.
+pub(crate) trait CacheStore: Debug + Send + Sync + 'static {
+    /// Version stored with `key` and how long ago it was stored or last
+    /// revalidated.
+    fn version(&self, key: &str) -> Option<(String, Duration)>;
+    /// Cached contents of `key`, marking it as recently used.
+    fn data(&self, key: &str) -> Option<Buffer>;
+    /// Records that `key` was just checked against the service.
+    fn revalidated(&self, key: &str);
+    fn put(&self, key: &str, version: &str, data: &Buffer);
+    fn remove(&self, key: &str);
+}
+
+/// Caches whole objects read through an operator.
+///
+/// Entries are keyed by service and path and tagged with the object's etag,
+/// or its size and modification time where the service has no etag. Every
+/// read stats the object and only serves the cached copy if the tag still
+/// matches, unless the entry was checked less than `ttl` ago. Objects the
+/// service reports neither for are only cached when there is a `ttl`.
+/// Reads of a specific version, conditional reads, and partial reads of
+/// uncached objects go straight to the service.
+pub(crate) struct CacheLayer<S> {
+    store: Arc<S>,
+    ttl: Option<Duration>,
+}
+
+impl<S> CacheLayer<S> {
+    pub(crate) fn new(store: S, ttl: Option<Duration>) -> Self {
+        CacheLayer {
+            store: Arc::new(store),
+            ttl,
+        }
+    }
+}
+
+impl<A: Access, S: CacheStore> Layer<A> for CacheLayer<S> {
+    type LayeredAccess = CacheAccessor<A, S>;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        let info = inner.info();
+        let cache = Cache {
+            store: self.store.clone(),
+            ttl: self.ttl,
+            namespace: format!("{}\n{}\n{}\n", info.scheme(), info.name(), info.root()),
+        };
+
+        CacheAccessor {
+            inner,
+            cache: Arc::new(cache),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Cache<S> {
+    store: Arc<S>,
+    ttl: Option<Duration>,
+    namespace: String,
+}
+
+impl<S: CacheStore> Cache<S> {
+    fn key(&self, path: &str) -> String {
+        format!(
+            "{:016x}",
+            fnv1a(format!("{}{path}", self.namespace).as_bytes())
+        )
+    }
+
+    /// A cached copy checked recently enough to skip asking the service.
+    fn fresh(&self, key: &str) -> Option<Buffer> {
+        let ttl = self.ttl?;
+        let (_, age) = self.store.version(key)?;
+        if age >= ttl {
+            return None;
+        }
+        self.store.data(key)
+    }
+
+    /// A cached copy whose version still matches the service's.
+    fn validated(&self, key: &str, version: Option<&str>) -> Option<Buffer> {
+        let version = version?;
+        let (cached, _) = self.store.version(key)?;
+        if cached != version {
+            return None;
+        }
+        let data = self.store.data(key)?;
+        self.store.revalidated(key);
+        Some(data)
+    }
+
+    /// Whether a read of `range` should fetch and store the whole object.
+    /// Objects without a version can't be revalidated, so they are only
+    /// kept when a `ttl` bounds how long they are trusted.
+    fn fills(&self, version: Option<&str>, range: BytesRange, meta: &Metadata) -> bool {
+        (version.is_some() || self.ttl.is_some()) && covers(range, meta.content_length())
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// What identifies this revision of an object, if the service says.
+fn version_of(meta: &Metadata) -> Option<String> {
+    if let Some(etag) = meta.etag() {
+        return Some(etag.to_string());
+    }
+    let modified = meta.last_modified()?;
+    Some(format!(
+        "{}@{}",
+        meta.content_length(),
+        modified.to_rfc3339()
+    ))
+}
+
+/// Reads that must not be answered from the cache.
+fn bypass(args: &OpRead) -> bool {
+    args.version().is_some()
+        || args.if_match().is_some()
+        || args.if_none_match().is_some()
+        || args.if_modified_since().is_some()
+        || args.if_unmodified_since().is_some()
+}
+
+/// Whether `range` asks for every byte of an object of `len` bytes.
+fn covers(range: BytesRange, len: u64) -> bool {
+    range.offset() == 0 && range.size().map_or(true, |size| size >= len)
+}
+
+/// The part of a cached object that `range` asks for.
+fn served(data: Buffer, range: BytesRange) -> (RpRead, Buffer) {
+    let len = data.len() as u64;
+    let start = range.offset().min(len);
+    let end = range.size().map_or(len, |size| (start + size).min(len));
+    let data = data.slice(start as usize..end as usize);
+
+    (RpRead::new().with_size(Some(data.len() as u64)), data)
+}
+
+#[derive(Debug)]
+pub(crate) struct CacheAccessor<A: Access, S> {
+    inner: A,
+    cache: Arc<Cache<S>>,
+}
+
+impl<A: Access, S: CacheStore> LayeredAccess for CacheAccessor<A, S> {
+    type Inner = A;
+    type Reader = TwoWays<Buffer, A::Reader>;
+    type BlockingReader = TwoWays<Buffer, A::BlockingReader>;
+    type Writer = A::Writer;
+    type BlockingWriter = A::BlockingWriter;
+    type Lister = A::Lister;
+    type BlockingLister = A::BlockingLister;
+    type Deleter = CacheDeleter<A::Deleter, S>;
+    type BlockingDeleter = CacheDeleter<A::BlockingDeleter, S>;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        let range = args.range();
+        let uncached = |(rp, r)| (rp, TwoWays::Two(r));
+        if bypass(&args) {
+            return self.inner.read(path, args).await.map(uncached);
+        }
+
+        let key = self.cache.key(path);
+        if let Some(data) = self.cache.fresh(&key) {
+            let (rp, data) = served(data, range);
+            return Ok((rp, TwoWays::One(data)));
+        }
+
+        let meta = self.inner.stat(path, OpStat::new()).await?.into_metadata();
+        let version = version_of(&meta);
+        if let Some(data) = self.cache.validated(&key, version.as_deref()) {
+            let (rp, data) = served(data, range);
+            return Ok((rp, TwoWays::One(data)));
+        }
+        if !self.cache.fills(version.as_deref(), range, &meta) {
+            return self.inner.read(path, args).await.map(uncached);
+        }
+
+        let whole = args.with_range(BytesRange::default());
+        let (_, mut reader) = self.inner.read(path, whole).await?;
+        let data = oio::Read::read_all(&mut reader).await?;
+        self.cache
+            .store
+            .put(&key, version.as_deref().unwrap_or(""), &data);
+
+        let (rp, data) = served(data, range);
+        Ok((rp, TwoWays::One(data)))
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        self.cache.store.remove(&self.cache.key(path));
+        self.inner.write(path, args).await
+    }
+
+    async fn copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        self.cache.store.remove(&self.cache.key(to));
+        self.inner.copy(from, to, args).await
+    }
+
+    async fn rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        self.cache.store.remove(&self.cache.key(from));
+        self.cache.store.remove(&self.cache.key(to));
+        self.inner.rename(from, to, args).await
+    }
+
+    async fn delete(&self) -> Result<(RpDelete, Self::Deleter)> {
+        let (rp, deleter) = self.inner.delete().await?;
+        Ok((rp, CacheDeleter::new(self.cache.clone(), deleter)))
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Lister)> {
+        self.inner.list(path, args).await
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        let range = args.range();
+        let uncached = |(rp, r)| (rp, TwoWays::Two(r));
+        if bypass(&args) {
+            return self.inner.blocking_read(path, args).map(uncached);
+        }
+
+        let key = self.cache.key(path);
+        if let Some(data) = self.cache.fresh(&key) {
+            let (rp, data) = served(data, range);
+            return Ok((rp, TwoWays::One(data)));
+        }
+
+        let meta = self
+            .inner
+            .blocking_stat(path, OpStat::new())?
+            .into_metadata();
+        let version = version_of(&meta);
+        if let Some(data) = self.cache.validated(&key, version.as_deref()) {
+            let (rp, data) = served(data, range);
+            return Ok((rp, TwoWays::One(data)));
+        }
+        if !self.cache.fills(version.as_deref(), range, &meta) {
+            return self.inner.blocking_read(path, args).map(uncached);
+        }
+
+        let whole = args.with_range(BytesRange::default());
+        let (_, mut reader) = self.inner.blocking_read(path, whole)?;
+        let mut chunks = Vec::new();
+        loop {
+            let chunk = oio::BlockingRead::read(&mut reader)?;
+            if chunk.is_empty() {
+                break;
+            }
+            chunks.push(chunk);
+        }
+        let data: Buffer = chunks.into_iter().flatten().collect();
+        self.cache
+            .store
+            .put(&key, version.as_deref().unwrap_or(""), &data);
+
+        let (rp, data) = served(data, range);
+        Ok((rp, TwoWays::One(data)))
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        self.cache.store.remove(&self.cache.key(path));
+        self.inner.blocking_write(path, args)
+    }
+
+    fn blocking_copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        self.cache.store.remove(&self.cache.key(to));
+        self.inner.blocking_copy(from, to, args)
+    }
+
+    fn blocking_rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        self.cache.store.remove(&self.cache.key(from));
+        self.cache.store.remove(&self.cache.key(to));
+        self.inner.blocking_rename(from, to, args)
+    }
+
+    fn blocking_delete(&self) -> Result<(RpDelete, Self::BlockingDeleter)> {
+        let (rp, deleter) = self.inner.blocking_delete()?;
+        Ok((rp, CacheDeleter::new(self.cache.clone(), deleter)))
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingLister)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+/// Drops cache entries for paths as they are queued for deletion.
+pub(crate) struct CacheDeleter<D, S> {
+    cache: Arc<Cache<S>>,
+    inner: D,
+}
+
+impl<D, S: CacheStore> CacheDeleter<D, S> {
+    fn new(cache: Arc<Cache<S>>, inner: D) -> Self {
+        CacheDeleter { cache, inner }
+    }
+
+    fn forget(&self, path: &str) {
+        self.cache.store.remove(&self.cache.key(path));
+    }
+}
+
+impl<D: oio::Delete, S: CacheStore> oio::Delete for CacheDeleter<D, S> {
+    fn delete(&mut self, path: &str, args: OpDelete) -> Result<()> {
+        self.forget(path);
+        self.inner.delete(path, args)
+    }
+
+    async fn flush(&mut self) -> Result<usize> {
+        self.inner.flush().await
+    }
+}
+
+impl<D: oio::BlockingDelete, S: CacheStore> oio::BlockingDelete for CacheDeleter<D, S> {
+    fn delete(&mut self, path: &str, args: OpDelete) -> Result<()> {
+        self.forget(path);
+        self.inner.delete(path, args)
+    }
+
+    fn flush(&mut self) -> Result<usize> {
+        self.inner.flush()
+    }
+}
+
+/// Keeps each entry as `<key>.bin` with its version in `<key>.ver`. The
+/// `.ver` file's mtime records the last revalidation and the `.bin` file's
+/// the last use, which decides what is evicted once `max_bytes` is reached.
+#[derive(Debug)]
+pub(crate) struct DiskStore {
+    dir: PathBuf,
+    max_bytes: Option<u64>,
+    // Serialises eviction scans with writes from this process.
+    lock: Mutex<()>,
+}
+
+impl DiskStore {
+    pub(crate) fn new(dir: PathBuf, max_bytes: Option<u64>) -> std::io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(DiskStore {
+            dir,
+            max_bytes,
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn path(&self, key: &str, ext: &str) -> PathBuf {
+        self.dir.join(format!("{key}.{ext}"))
+    }
+
+    fn write_file(&self, key: &str, ext: &str, data: &Buffer) -> std::io::Result<()> {
+        let tmp = self.path(key, &format!("{ext}.tmp"));
+        let mut file = File::create(&tmp)?;
+        for chunk in data.clone() {
+            file.write_all(&chunk)?;
+        }
+        fs::rename(tmp, self.path(key, ext))
+    }
+
+    /// Removes least recently used entries until the cache fits again.
+    fn evict(&self) {
+        let Some(max) = self.max_bytes else {
+            return;
+        };
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        let mut entries: Vec<(SystemTime, u64, String)> = dir
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let key = name.strip_suffix(".bin")?.to_string();
+                let meta = entry.metadata().ok()?;
+                Some((meta.modified().ok()?, meta.len(), key))
+            })
+            .collect();
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+
+        entries.sort();
+        for (_, len, key) in entries {
+            if total <= max {
+                break;
+            }
+            self.remove(&key);
+            total -= len;
+        }
+    }
+}
+
+fn touch(path: PathBuf) {
+    if let Ok(file) = OpenOptions::new().append(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+impl CacheStore for DiskStore {
+    fn version(&self, key: &str) -> Option<(String, Duration)> {
+        let path = self.path(key, "ver");
+        let version = fs::read_to_string(&path).ok()?;
+        let age = fs::metadata(&path).ok()?.modified().ok()?.elapsed();
+        Some((version, age.unwrap_or_default()))
+    }
+
+    fn data(&self, key: &str) -> Option<Buffer> {
+        let path = self.path(key, "bin");
+        let data = fs::read(&path).ok()?;
+        touch(path);
+        Some(Buffer::from(data))
+    }
+
+    fn revalidated(&self, key: &str) {
+        touch(self.path(key, "ver"));
+    }
+
+    fn put(&self, key: &str, version: &str, data: &Buffer) {
+        if self.max_bytes.is_some_and(|max| data.len() as u64 > max) {
+            return;
+        }
+        let Ok(_guard) = self.lock.lock() else {
+            return;
+        };
+
+        let version = Buffer::from(version.to_string());
+        let stored = self
+            .write_file(key, "bin", data)
+            .and_then(|_| self.write_file(key, "ver", &version));
+        if stored.is_err() {
+            self.remove(key);
+            return;
+        }
+        self.evict();
+    }
+
+    fn remove(&self, key: &str) {
+        let _ = fs::remove_file(self.path(key, "ver"));
+        let _ = fs::remove_file(self.path(key, "bin"));
+    }
+}
+
+/// Keeps entries in memory for the session, evicting the least recently
+/// used once `max_bytes` is reached.
+#[derive(Debug)]
+pub(crate) struct MemoryStore {
+    max_bytes: u64,
+    entries: Mutex<MemoryEntries>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryEntries {
+    map: HashMap<String, MemoryEntry>,
+    total: u64,
+    // Bumped on every use; the entry with the lowest stamp goes first.
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct MemoryEntry {
+    version: String,
+    checked: Instant,
+    used: u64,
+    data: Buffer,
+}
+
+impl MemoryStore {
+    pub(crate) fn new(max_bytes: u64) -> Self {
+        MemoryStore {
+            max_bytes,
+            entries: Mutex::default(),
+        }
+    }
+}
+
+impl MemoryEntries {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.map.remove(key) {
+            self.total -= entry.data.len() as u64;
+        }
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn version(&self, key: &str) -> Option<(String, Duration)> {
+        let entries = self.entries.lock().ok()?;
+        let entry = entries.map.get(key)?;
+        Some((entry.version.clone(), entry.checked.elapsed()))
+    }
+
+    fn data(&self, key: &str) -> Option<Buffer> {
+        let mut entries = self.entries.lock().ok()?;
+        let used = entries.tick();
+        let entry = entries.map.get_mut(key)?;
+        entry.used = used;
+        Some(entry.data.clone())
+    }
+
+    fn revalidated(&self, key: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            if let Some(entry) = entries.map.get_mut(key) {
+                entry.checked = Instant::now();
+            }
+        }
+    }
+
+    fn put(&self, key: &str, version: &str, data: &Buffer) {
+        let len = data.len() as u64;
+        if len > self.max_bytes {
+            return;
+        }
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+
+        entries.remove(key);
+        while entries.total + len > self.max_bytes {
+            let oldest = entries
+                .map
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => break,
+            }
+        }
+
+        let used = entries.tick();
+        entries.total += len;
+        entries.map.insert(
+            key.to_string(),
+            MemoryEntry {
+                version: version.to_string(),
+                checked: Instant::now(),
+                used,
+                // Copy out of the response so a small object doesn't pin
+                // the larger buffer it may have been read into.
+                data: Buffer::from(data.to_vec()),
+            },
+        );
+    }
+
+    fn remove(&self, key: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(key);
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use std::ffi::{c_char, c_int, c_short, c_void, CStr, CString};
+use std::io::{Read, Write};
+
+use anyhow::Result;
+use extendr_api::prelude::*;
+use extendr_api::SEXP;
+use opendal::{BlockingOperator, StdReader, StdWriter};
+
+/// The prefix of `struct Rconn` from `R_ext/Connections.h`, version 1.
+///
+/// R only hands out pointers to connections it allocated itself, so just the
+/// fields up to `private` need to line up; the ones after it are never used.
+#[repr(C)]
+struct Rconn {
+    class: *mut c_char,
+    description: *mut c_char,
+    enc: c_int,
+    mode: [c_char; 5],
+    text: c_int,
+    isopen: c_int,
+    incomplete: c_int,
+    canread: c_int,
+    canwrite: c_int,
+    canseek: c_int,
+    blocking: c_int,
+    is_gzcon: c_int,
+    open: Option<unsafe extern "C" fn(*mut Rconn) -> c_int>,
+    close: Option<unsafe extern "C" fn(*mut Rconn)>,
+    destroy: Option<unsafe extern "C" fn(*mut Rconn)>,
+    vfprintf: *mut c_void,
+    fgetc: Option<unsafe extern "C" fn(*mut Rconn) -> c_int>,
+    fgetc_internal: Option<unsafe extern "C" fn(*mut Rconn) -> c_int>,
+    seek: *mut c_void,
+    truncate: *mut c_void,
+    fflush: *mut c_void,
+    read: Option<unsafe extern "C" fn(*mut c_void, usize, usize, *mut Rconn) -> usize>,
+    write: Option<unsafe extern "C" fn(*const c_void, usize, usize, *mut Rconn) -> usize>,
+    n_push_back: c_int,
+    pos_push_back: c_int,
+    push_back: *mut *mut c_char,
+    save: c_int,
+    save2: c_int,
+    encname: [c_char; 101],
+    inconv: *mut c_void,
+    outconv: *mut c_void,
+    iconvbuff: [c_char; 25],
+    oconvbuff: [c_char; 50],
+    next: *mut c_char,
+    init_out: [c_char; 25],
+    navail: c_short,
+    inavail: c_short,
+    eof_signalled: c_int,
+    utf8out: c_int,
+    id: *mut c_void,
+    ex_ptr: *mut c_void,
+    private: *mut c_void,
+}
+
+extern "C" {
+    fn R_new_custom_connection(
+        description: *const c_char,
+        mode: *const c_char,
+        class_name: *const c_char,
+        ptr: *mut *mut Rconn,
+    ) -> *mut c_void;
+    fn Rf_error(fmt: *const c_char, ...) -> !;
+    fn Rf_warning(fmt: *const c_char, ...);
+}
+
+/// An open object, read or written as R asks.
+enum Stream {
+    Closed,
+    Read(StdReader),
+    Write(StdWriter),
+}
+
+/// What a connection reads from or writes to, owned by the connection's
+/// `private` slot.
+struct Source {
+    op: BlockingOperator,
+    path: String,
+    write: bool,
+    stream: Stream,
+    /// The last failure, kept here so it outlives the callback that
+    /// raises it: R errors unwind with a longjmp, which skips destructors.
+    error: CString,
+}
+
+impl Source {
+    fn open(&mut self) -> Result<()> {
+        self.stream = if self.write {
+            Stream::Write(self.op.writer(&self.path)?.into_std_write())
+        } else {
+            Stream::Read(self.op.reader(&self.path)?.into_std_read(..)?)
+        };
+        Ok(())
+    }
+
+    /// Fills as much of `buf` as the object has left.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let Stream::Read(reader) = &mut self.stream else {
+            anyhow::bail!("connection to `{}` is not open for reading", self.path);
+        };
+        let mut filled = 0;
+        while filled < buf.len() {
+            match reader.read(&mut buf[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        Ok(filled)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> Result<()> {
+        let Stream::Write(writer) = &mut self.stream else {
+            anyhow::bail!("connection to `{}` is not open for writing", self.path);
+        };
+        writer.write_all(buf)?;
+        Ok(())
+    }
+
+    /// Commits a written object; a read one is simply dropped.
+    fn close(&mut self) -> Result<()> {
+        if let Stream::Write(mut writer) = std::mem::replace(&mut self.stream, Stream::Closed) {
+            writer.close()?;
+        }
+        Ok(())
+    }
+
+    fn fail(&mut self, e: anyhow::Error) -> *const c_char {
+        self.error = CString::new(e.to_string().replace('\0', "")).unwrap_or_default();
+        self.error.as_ptr()
+    }
+}
+
+unsafe fn source<'a>(con: *mut Rconn) -> &'a mut Source {
+    &mut *((*con).private as *mut Source)
+}
+
+const FORMAT: &CStr = c"%s";
+
+/// What `fgetc` returns at the end of the object.
+const R_EOF: c_int = -1;
+
+// R raises errors and, under `options(warn = 2)`, warnings by longjmp-ing
+// out of the callback, which skips Rust destructors. So each callback does
+// its work in a closure that returns only plain values, with any error
+// message left in `Source::error`, and only calls `Rf_error` or
+// `Rf_warning` once nothing that needs dropping is live. The connection
+// itself is freed later through `con_destroy`.
+
+unsafe extern "C" fn con_open(con: *mut Rconn) -> c_int {
+    let opened: std::result::Result<(), *const c_char> = {
+        let source = source(con);
+        source.open().map_err(|e| source.fail(e))
+    };
+    match opened {
+        Ok(()) => {
+            // R copies the mode it was opened with into `mode` beforehand.
+            (*con).text = !CStr::from_ptr((*con).mode.as_ptr())
+                .to_bytes()
+                .contains(&b'b') as c_int;
+            (*con).isopen = 1;
+            1
+        }
+        Err(msg) => {
+            Rf_warning(FORMAT.as_ptr(), msg);
+            0
+        }
+    }
+}
+
+unsafe extern "C" fn con_close(con: *mut Rconn) {
+    (*con).isopen = 0;
+    let closed: std::result::Result<(), *const c_char> = {
+        let source = source(con);
+        source.close().map_err(|e| source.fail(e))
+    };
+    if let Err(msg) = closed {
+        Rf_error(FORMAT.as_ptr(), msg)
+    }
+}
+
+unsafe extern "C" fn con_destroy(con: *mut Rconn) {
+    drop(Box::from_raw((*con).private as *mut Source));
+    (*con).private = std::ptr::null_mut();
+}
+
+unsafe extern "C" fn con_read(ptr: *mut c_void, size: usize, n: usize, con: *mut Rconn) -> usize {
+    let Some(len) = size.checked_mul(n).filter(|_| size > 0) else {
+        return 0;
+    };
+    let read: std::result::Result<usize, *const c_char> = {
+        let source = source(con);
+        let buf = std::slice::from_raw_parts_mut(ptr as *mut u8, len);
+        source.read(buf).map_err(|e| source.fail(e))
+    };
+    match read {
+        Ok(filled) => filled / size,
+        Err(msg) => Rf_error(FORMAT.as_ptr(), msg),
+    }
+}
+
+/// Reads one byte, which text-mode functions such as `readLines()` and
+/// `read.csv()` use instead of `read`.
+unsafe extern "C" fn con_fgetc(con: *mut Rconn) -> c_int {
+    let read: std::result::Result<(usize, u8), *const c_char> = {
+        let source = source(con);
+        let mut byte = [0u8; 1];
+        match source.read(&mut byte) {
+            Ok(n) => Ok((n, byte[0])),
+            Err(e) => Err(source.fail(e)),
+        }
+    };
+    match read {
+        Ok((0, _)) => R_EOF,
+        Ok((_, byte)) => byte as c_int,
+        Err(msg) => Rf_error(FORMAT.as_ptr(), msg),
+    }
+}
+
+unsafe extern "C" fn con_write(
+    ptr: *const c_void,
+    size: usize,
+    n: usize,
+    con: *mut Rconn,
+) -> usize {
+    let Some(len) = size.checked_mul(n).filter(|_| size > 0) else {
+        return 0;
+    };
+    let written: std::result::Result<(), *const c_char> = {
+        let source = source(con);
+        let buf = std::slice::from_raw_parts(ptr as *const u8, len);
+        source.write(buf).map_err(|e| source.fail(e))
+    };
+    match written {
+        Ok(()) => n,
+        Err(msg) => Rf_error(FORMAT.as_ptr(), msg),
+    }
+}
+
+/// Opens `path` as an R connection of class `opendal`.
+///
+/// `"rb"` and `"wb"` give binary connections for `readRDS()`, `saveRDS()`
+/// and friends, `"r"`/`"rt"` and `"w"`/`"wt"` text ones for `readLines()`,
+/// `read.csv()` or `writeLines()`. Data is streamed as R asks for it.
+///
+/// Read connections are returned open. Write connections are returned
+/// unopened, like `file(path)`, so that `saveRDS()` and similar functions
+/// open the upload themselves and commit it when they close it; to write
+/// in several calls, `open()` the connection first and `close()` it after.
+pub(crate) fn open(op: BlockingOperator, path: &str, mode: &str) -> Result<Robj> {
+    let (write, text) = match mode {
+        "rb" => (false, false),
+        "r" | "rt" => (false, true),
+        "wb" => (true, false),
+        "w" | "wt" => (true, true),
+        _ => anyhow::bail!(
+            "`mode` must be one of \"rb\", \"r\", \"rt\", \"wb\", \"w\" or \"wt\", not \"{mode}\""
+        ),
+    };
+
+    let mut source = Source {
+        op,
+        path: path.to_string(),
+        write,
+        stream: Stream::Closed,
+        error: CString::default(),
+    };
+    if !write {
+        source.open()?;
+    }
+
+    let description = CString::new(path)?;
+    let mode = CString::new(mode)?;
+    let mut con: *mut Rconn = std::ptr::null_mut();
+    unsafe {
+        let sexp = R_new_custom_connection(
+            description.as_ptr(),
+            mode.as_ptr(),
+            c"opendal".as_ptr(),
+            &mut con,
+        );
+        let robj = Robj::from_sexp(sexp as SEXP);
+
+        (*con).private = Box::into_raw(Box::new(source)) as *mut c_void;
+        (*con).text = text as c_int;
+        (*con).isopen = !write as c_int;
+        (*con).canread = !write as c_int;
+        (*con).canwrite = write as c_int;
+        (*con).canseek = 0;
+        (*con).blocking = 1;
+        (*con).open = Some(con_open);
+        (*con).close = Some(con_close);
+        (*con).destroy = Some(con_destroy);
+        (*con).read = Some(con_read);
+        (*con).fgetc = Some(con_fgetc);
+        (*con).fgetc_internal = Some(con_fgetc);
+        (*con).write = Some(con_write);
+        Ok(robj)
+    }
+}
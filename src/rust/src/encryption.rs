@@ -0,0 +1,479 @@
+use std::fmt::{self, Debug, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use opendal::raw::*;
+use opendal::{Buffer, EntryMode, Error, ErrorKind, Metadata, Result};
+
+/// Plaintext bytes sealed per chunk.
+const CHUNK: u64 = 64 * 1024;
+/// Authentication tag appended to each sealed chunk.
+const TAG: u64 = 16;
+const SEALED: u64 = CHUNK + TAG;
+/// Magic followed by the object's random nonce prefix.
+const MAGIC: &[u8; 8] = b"ODLRENC1";
+const HEADER: u64 = 16;
+
+/// Ciphers `with_encryption()` can use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Algorithm {
+    Aes256Gcm,
+}
+
+impl FromStr for Algorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "aes-256-gcm" => Ok(Algorithm::Aes256Gcm),
+            other => anyhow::bail!("unknown encryption algorithm `{other}`; expected aes-256-gcm"),
+        }
+    }
+}
+
+/// Encrypts objects on write and decrypts them on read.
+///
+/// An object is stored as a 16-byte header (magic and a random 8-byte nonce
+/// prefix) followed by 64 KiB chunks, each sealed with AES-256-GCM under the
+/// prefix and its chunk number. The last chunk is marked in its associated
+/// data, so reordered, truncated or extended objects fail to decrypt. Reads
+/// stat the object for its stored size, then fetch and decrypt only the
+/// chunks the requested range covers.
+///
+/// `stat()` reports plaintext sizes, but sizes in list results are the
+/// stored, encrypted ones. Appends are rejected.
+pub(crate) struct EncryptionLayer {
+    cipher: Arc<Aes256Gcm>,
+}
+
+impl EncryptionLayer {
+    pub(crate) fn new(algorithm: Algorithm, key: &[u8]) -> anyhow::Result<Self> {
+        let cipher = match algorithm {
+            Algorithm::Aes256Gcm => Aes256Gcm::new_from_slice(key)
+                .map_err(|_| anyhow::anyhow!("aes-256-gcm needs a 32-byte key"))?,
+        };
+
+        Ok(EncryptionLayer {
+            cipher: Arc::new(cipher),
+        })
+    }
+}
+
+impl<A: Access> Layer<A> for EncryptionLayer {
+    type LayeredAccess = EncryptionAccessor<A>;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        // Shared with the parent operator, so report no appends on a copy.
+        let parent = inner.info();
+        let info = AccessorInfo::default();
+        info.set_scheme(parent.scheme())
+            .set_name(&parent.name())
+            .set_root(&parent.root())
+            .set_native_capability(parent.native_capability());
+        info.update_full_capability(|_| {
+            let mut cap = parent.full_capability();
+            cap.write_can_append = false;
+            cap
+        });
+
+        EncryptionAccessor {
+            inner,
+            info: Arc::new(info),
+            cipher: self.cipher.clone(),
+        }
+    }
+}
+
+pub(crate) struct EncryptionAccessor<A: Access> {
+    inner: A,
+    info: Arc<AccessorInfo>,
+    cipher: Arc<Aes256Gcm>,
+}
+
+// Hand-written so the key can't end up in debug output.
+impl<A: Access> Debug for EncryptionAccessor<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptionAccessor")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: Access> EncryptionAccessor<A> {
+    fn writer<W>(&self, args: &OpWrite, inner: W) -> Result<EncryptWriter<W>> {
+        if args.append() {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "encrypted objects can't be appended to",
+            ));
+        }
+        Ok(EncryptWriter::new(inner, self.cipher.clone()))
+    }
+}
+
+/// The ciphertext needed to serve a plaintext range.
+struct Plan {
+    /// First chunk covered.
+    first: u64,
+    /// Whether the last chunk covered is the object's last.
+    eof: bool,
+    /// Ciphertext to fetch, starting at the header when `first` is 0.
+    range: BytesRange,
+    skip: usize,
+    take: usize,
+}
+
+impl Plan {
+    /// Plans a read of `range` from an object stored as `stored` bytes, or
+    /// `None` if the range is empty.
+    fn new(range: BytesRange, stored: u64) -> Result<Option<Self>> {
+        let plain = plain_len(stored).ok_or_else(|| corrupted("too short"))?;
+        let offset = range.offset();
+        let end = range
+            .size()
+            .map_or(plain, |size| offset.saturating_add(size).min(plain));
+        if offset >= end {
+            return Ok(None);
+        }
+
+        let first = offset / CHUNK;
+        let last = (end - 1) / CHUNK;
+        let start = HEADER + first * SEALED;
+        let stop = (HEADER + (last + 1) * SEALED).min(stored);
+        let range = if first == 0 {
+            BytesRange::new(0, Some(stop))
+        } else {
+            BytesRange::new(start, Some(stop - start))
+        };
+
+        Ok(Some(Plan {
+            first,
+            eof: last + 1 == plain.div_ceil(CHUNK).max(1),
+            range,
+            skip: (offset - first * CHUNK) as usize,
+            take: (end - offset) as usize,
+        }))
+    }
+
+    fn header_range(&self) -> Option<BytesRange> {
+        (self.first != 0).then(|| BytesRange::new(0, Some(HEADER)))
+    }
+}
+
+fn corrupted(msg: &str) -> Error {
+    Error::new(
+        ErrorKind::Unexpected,
+        format!("can't decrypt object: {msg}; wrong key or corrupted data"),
+    )
+}
+
+fn nonce(prefix: &[u8], chunk: u64) -> Result<[u8; 12]> {
+    let chunk = u32::try_from(chunk).map_err(|_| corrupted("too many chunks"))?;
+    let mut nonce = [0; 12];
+    nonce[..8].copy_from_slice(prefix);
+    nonce[8..].copy_from_slice(&chunk.to_be_bytes());
+    Ok(nonce)
+}
+
+/// Decrypts the chunks `plan` fetched. `header` is `None` when it leads
+/// `body`.
+fn decrypt(
+    cipher: &Aes256Gcm,
+    plan: &Plan,
+    header: Option<Buffer>,
+    body: Buffer,
+) -> Result<Buffer> {
+    let mut body = body.to_vec();
+    let header = match header {
+        Some(header) => header.to_vec(),
+        None => body.drain(..body.len().min(HEADER as usize)).collect(),
+    };
+    if header.len() != HEADER as usize || &header[..8] != MAGIC {
+        return Err(corrupted("missing encryption header"));
+    }
+    let expected = plan.range.size().unwrap_or_default() - if plan.first == 0 { HEADER } else { 0 };
+    if body.len() as u64 != expected {
+        return Err(corrupted("short read"));
+    }
+
+    let sealed: Vec<&[u8]> = body.chunks(SEALED as usize).collect();
+    let mut plain = Vec::with_capacity(body.len());
+    for (i, chunk) in sealed.iter().enumerate() {
+        let last = plan.eof && i == sealed.len() - 1;
+        let nonce = nonce(&header[8..], plan.first + i as u64)?;
+        let payload = Payload {
+            msg: chunk,
+            aad: &[last as u8],
+        };
+        let opened = cipher
+            .decrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| corrupted("authentication failed"))?;
+        plain.extend_from_slice(&opened);
+    }
+
+    let start = plan.skip.min(plain.len());
+    let end = (start + plan.take).min(plain.len());
+    Ok(Buffer::from(plain).slice(start..end))
+}
+
+/// Plaintext size of an encrypted object of `len` bytes.
+fn plain_len(len: u64) -> Option<u64> {
+    let body = len.checked_sub(HEADER)?;
+    let chunks = body.div_ceil(SEALED).max(1);
+    body.checked_sub(chunks * TAG)
+}
+
+fn decrypted_meta(mut meta: Metadata) -> Metadata {
+    if meta.mode() == EntryMode::FILE {
+        if let Some(len) = plain_len(meta.content_length()) {
+            meta.set_content_length(len);
+        }
+    }
+    meta
+}
+
+/// The stat for the stored size of the object `args` reads, which must be
+/// of the same version.
+fn stored_stat(args: &OpRead) -> OpStat {
+    match args.version() {
+        Some(v) => OpStat::new().with_version(v),
+        None => OpStat::new(),
+    }
+}
+
+fn read_response(data: Buffer) -> (RpRead, Buffer) {
+    (RpRead::new().with_size(Some(data.len() as u64)), data)
+}
+
+impl<A: Access> LayeredAccess for EncryptionAccessor<A> {
+    type Inner = A;
+    type Reader = Buffer;
+    type BlockingReader = Buffer;
+    type Writer = EncryptWriter<A::Writer>;
+    type BlockingWriter = EncryptWriter<A::BlockingWriter>;
+    type Lister = A::Lister;
+    type BlockingLister = A::BlockingLister;
+    type Deleter = A::Deleter;
+    type BlockingDeleter = A::BlockingDeleter;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    fn info(&self) -> Arc<AccessorInfo> {
+        self.info.clone()
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        let stored = self
+            .inner
+            .stat(path, stored_stat(&args))
+            .await?
+            .into_metadata()
+            .content_length();
+        let Some(plan) = Plan::new(args.range(), stored)? else {
+            return Ok(read_response(Buffer::new()));
+        };
+
+        let header = match plan.header_range() {
+            Some(range) => {
+                let (_, mut r) = self
+                    .inner
+                    .read(path, args.clone().with_range(range))
+                    .await?;
+                Some(oio::Read::read_all(&mut r).await?)
+            }
+            None => None,
+        };
+        let (_, mut r) = self.inner.read(path, args.with_range(plan.range)).await?;
+        let body = oio::Read::read_all(&mut r).await?;
+
+        decrypt(&self.cipher, &plan, header, body).map(read_response)
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        let (rp, writer) = self.inner.write(path, args.clone()).await?;
+        Ok((rp, self.writer(&args, writer)?))
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        let rp = self.inner.stat(path, args).await?;
+        Ok(RpStat::new(decrypted_meta(rp.into_metadata())))
+    }
+
+    async fn delete(&self) -> Result<(RpDelete, Self::Deleter)> {
+        self.inner.delete().await
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Lister)> {
+        self.inner.list(path, args).await
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        let stored = self
+            .inner
+            .blocking_stat(path, stored_stat(&args))?
+            .into_metadata()
+            .content_length();
+        let Some(plan) = Plan::new(args.range(), stored)? else {
+            return Ok(read_response(Buffer::new()));
+        };
+
+        let header = match plan.header_range() {
+            Some(range) => {
+                let (_, r) = self
+                    .inner
+                    .blocking_read(path, args.clone().with_range(range))?;
+                Some(read_all_blocking(r)?)
+            }
+            None => None,
+        };
+        let (_, r) = self
+            .inner
+            .blocking_read(path, args.with_range(plan.range))?;
+        let body = read_all_blocking(r)?;
+
+        decrypt(&self.cipher, &plan, header, body).map(read_response)
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        let (rp, writer) = self.inner.blocking_write(path, args.clone())?;
+        Ok((rp, self.writer(&args, writer)?))
+    }
+
+    fn blocking_stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        let rp = self.inner.blocking_stat(path, args)?;
+        Ok(RpStat::new(decrypted_meta(rp.into_metadata())))
+    }
+
+    fn blocking_delete(&self) -> Result<(RpDelete, Self::BlockingDeleter)> {
+        self.inner.blocking_delete()
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingLister)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+fn read_all_blocking(mut r: impl oio::BlockingRead) -> Result<Buffer> {
+    let mut chunks = Vec::new();
+    loop {
+        let chunk = r.read()?;
+        if chunk.is_empty() {
+            break;
+        }
+        chunks.push(chunk);
+    }
+    Ok(chunks.into_iter().flatten().collect())
+}
+
+/// Seals plaintext into chunks as it arrives. A full chunk is only sealed
+/// once more data follows it, since the last chunk is marked as such.
+pub(crate) struct EncryptWriter<W> {
+    inner: W,
+    cipher: Arc<Aes256Gcm>,
+    prefix: [u8; 8],
+    /// Next chunk number; the header goes out with chunk 0.
+    chunk: u64,
+    pending: Vec<u8>,
+    written: u64,
+}
+
+impl<W> EncryptWriter<W> {
+    fn new(inner: W, cipher: Arc<Aes256Gcm>) -> Self {
+        let mut prefix = [0; 8];
+        OsRng.fill_bytes(&mut prefix);
+
+        EncryptWriter {
+            inner,
+            cipher,
+            prefix,
+            chunk: 0,
+            pending: Vec::new(),
+            written: 0,
+        }
+    }
+
+    fn seal(&mut self, out: &mut Vec<u8>, plain: &[u8], last: bool) -> Result<()> {
+        if self.chunk == 0 {
+            out.extend_from_slice(MAGIC);
+            out.extend_from_slice(&self.prefix);
+        }
+        let nonce = nonce(&self.prefix, self.chunk)?;
+        let payload = Payload {
+            msg: plain,
+            aad: &[last as u8],
+        };
+        let sealed = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| Error::new(ErrorKind::Unexpected, "encryption failed"))?;
+        out.extend_from_slice(&sealed);
+        self.chunk += 1;
+        Ok(())
+    }
+
+    /// Takes `bs` in and returns whatever ciphertext is ready to send.
+    fn push(&mut self, bs: Buffer) -> Result<Buffer> {
+        self.written += bs.len() as u64;
+        for chunk in bs {
+            self.pending.extend_from_slice(&chunk);
+        }
+
+        let mut out = Vec::new();
+        while self.pending.len() as u64 > CHUNK {
+            let plain: Vec<u8> = self.pending.drain(..CHUNK as usize).collect();
+            self.seal(&mut out, &plain, false)?;
+        }
+        Ok(Buffer::from(out))
+    }
+
+    /// Seals what's left as the last chunk.
+    fn finish(&mut self) -> Result<Buffer> {
+        let plain = std::mem::take(&mut self.pending);
+        let mut out = Vec::new();
+        self.seal(&mut out, &plain, true)?;
+        Ok(Buffer::from(out))
+    }
+}
+
+impl<W: oio::Write> oio::Write for EncryptWriter<W> {
+    async fn write(&mut self, bs: Buffer) -> Result<()> {
+        let sealed = self.push(bs)?;
+        if !sealed.is_empty() {
+            self.inner.write(sealed).await?;
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<Metadata> {
+        let sealed = self.finish()?;
+        self.inner.write(sealed).await?;
+        let meta = self.inner.close().await?;
+        Ok(meta.with_content_length(self.written))
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+}
+
+impl<W: oio::BlockingWrite> oio::BlockingWrite for EncryptWriter<W> {
+    fn write(&mut self, bs: Buffer) -> Result<()> {
+        let sealed = self.push(bs)?;
+        if !sealed.is_empty() {
+            self.inner.write(sealed)?;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<Metadata> {
+        let sealed = self.finish()?;
+        self.inner.write(sealed)?;
+        let meta = self.inner.close()?;
+        Ok(meta.with_content_length(self.written))
+    }
+}
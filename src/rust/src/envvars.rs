@@ -0,0 +1,142 @@
+use std::env;
+
+use opendal::Scheme;
+
+use crate::uri::split_bucket;
+
+/// Conventional variables for each service option, in order of preference.
+const CONVENTIONAL: &[(Scheme, &str, &[&str])] = &[
+    (Scheme::S3, "access_key_id", &["AWS_ACCESS_KEY_ID"]),
+    (Scheme::S3, "secret_access_key", &["AWS_SECRET_ACCESS_KEY"]),
+    (Scheme::S3, "session_token", &["AWS_SESSION_TOKEN"]),
+    (Scheme::S3, "region", &["AWS_REGION", "AWS_DEFAULT_REGION"]),
+    (
+        Scheme::S3,
+        "endpoint",
+        &["AWS_ENDPOINT_URL_S3", "AWS_ENDPOINT_URL"],
+    ),
+    (Scheme::S3, "role_arn", &["AWS_ROLE_ARN"]),
+    (Scheme::S3, "role_session_name", &["AWS_ROLE_SESSION_NAME"]),
+    (
+        Scheme::Gcs,
+        "credential_path",
+        &["GOOGLE_APPLICATION_CREDENTIALS"],
+    ),
+    (
+        Scheme::Azdls,
+        "account_name",
+        &["AZURE_STORAGE_ACCOUNT_NAME", "AZURE_STORAGE_ACCOUNT"],
+    ),
+    (
+        Scheme::Azdls,
+        "account_key",
+        &["AZURE_STORAGE_ACCOUNT_KEY", "AZURE_STORAGE_KEY"],
+    ),
+    (Scheme::Azdls, "sas_token", &["AZURE_STORAGE_SAS_TOKEN"]),
+    (Scheme::Azdls, "endpoint", &["AZURE_STORAGE_ENDPOINT"]),
+    (Scheme::Azdls, "tenant_id", &["AZURE_TENANT_ID"]),
+    (Scheme::Azdls, "client_id", &["AZURE_CLIENT_ID"]),
+    (Scheme::Azdls, "client_secret", &["AZURE_CLIENT_SECRET"]),
+    (Scheme::Azdls, "authority_host", &["AZURE_AUTHORITY_HOST"]),
+    (Scheme::Cos, "secret_id", &["TENCENTCLOUD_SECRET_ID"]),
+    (Scheme::Cos, "secret_key", &["TENCENTCLOUD_SECRET_KEY"]),
+    (Scheme::Cos, "endpoint", &["COS_ENDPOINT"]),
+    (Scheme::Obs, "access_key_id", &["OBS_ACCESS_KEY_ID"]),
+    (Scheme::Obs, "secret_access_key", &["OBS_SECRET_ACCESS_KEY"]),
+    (Scheme::Obs, "endpoint", &["OBS_ENDPOINT"]),
+    (
+        Scheme::Huggingface,
+        "token",
+        &["HF_TOKEN", "HUGGING_FACE_HUB_TOKEN"],
+    ),
+    (Scheme::Github, "token", &["GITHUB_TOKEN", "GH_TOKEN"]),
+    (Scheme::Dropbox, "access_token", &["DROPBOX_ACCESS_TOKEN"]),
+    (Scheme::Dbfs, "endpoint", &["DATABRICKS_HOST"]),
+    (Scheme::Dbfs, "token", &["DATABRICKS_TOKEN"]),
+    (Scheme::Lakefs, "endpoint", &["LAKECTL_SERVER_ENDPOINT_URL"]),
+    (
+        Scheme::Lakefs,
+        "username",
+        &["LAKECTL_CREDENTIALS_ACCESS_KEY_ID"],
+    ),
+    (
+        Scheme::Lakefs,
+        "password",
+        &["LAKECTL_CREDENTIALS_SECRET_ACCESS_KEY"],
+    ),
+    (Scheme::VercelBlob, "token", &["BLOB_READ_WRITE_TOKEN"]),
+    (Scheme::Hdfs, "user", &["HADOOP_USER_NAME"]),
+    (Scheme::Webhdfs, "user_name", &["HADOOP_USER_NAME"]),
+    (Scheme::Redis, "endpoint", &["REDIS_URL"]),
+    (Scheme::Postgresql, "connection_string", &["DATABASE_URL"]),
+    (Scheme::Mysql, "connection_string", &["DATABASE_URL"]),
+];
+
+/// Collects options for `scheme` from the environment.
+///
+/// The service's conventional variables are read first, then any
+/// `OPENDAL_<SCHEME>_<OPTION>` variable, e.g. `OPENDAL_S3_BUCKET`, which sets
+/// `<option>` directly and wins over the conventional one. When no bucket
+/// (or Azure filesystem) is configured, the first segment of `root` names it.
+pub(crate) fn load_env(scheme: Scheme, root: Option<&str>) -> Vec<(String, String)> {
+    let mut options = Vec::new();
+
+    for (_, key, vars) in CONVENTIONAL.iter().filter(|(s, _, _)| *s == scheme) {
+        if let Some(value) = vars.iter().find_map(|var| non_empty_var(var)) {
+            set(&mut options, key, value);
+        }
+    }
+
+    let prefix = format!(
+        "OPENDAL_{}_",
+        scheme.to_string().to_uppercase().replace('-', "_")
+    );
+    for (var, value) in env::vars() {
+        if let Some(key) = var.strip_prefix(&prefix) {
+            if !value.is_empty() {
+                set(&mut options, &key.to_lowercase(), value);
+            }
+        }
+    }
+
+    let bucket_key = match scheme {
+        Scheme::S3 | Scheme::Gcs | Scheme::Cos | Scheme::Obs => Some("bucket"),
+        Scheme::Azdls => Some("filesystem"),
+        _ => None,
+    };
+    let root = match (bucket_key, root) {
+        (Some(key), Some(path)) if !options.iter().any(|(k, _)| k == key) => {
+            match split_bucket(path) {
+                Some((bucket, root)) => {
+                    set(&mut options, key, bucket);
+                    Some(root)
+                }
+                None => Some(path.to_string()),
+            }
+        }
+        (_, root) => root.map(str::to_string),
+    };
+    if let Some(root) = root {
+        set(&mut options, "root", root);
+    }
+
+    if scheme == Scheme::Azdls && !options.iter().any(|(k, _)| k == "endpoint") {
+        let account = options.iter().find(|(k, _)| k == "account_name");
+        if let Some((_, account)) = account {
+            let endpoint = format!("https://{account}.dfs.core.windows.net");
+            options.push(("endpoint".to_string(), endpoint));
+        }
+    }
+
+    options
+}
+
+/// Sets `key`, replacing any earlier value.
+fn set(options: &mut Vec<(String, String)>, key: &str, value: String) {
+    options.retain(|(k, _)| k != key);
+    options.push((key.to_string(), value));
+}
+
+fn non_empty_var(var: &str) -> Option<String> {
+    env::var(var).ok().filter(|v| !v.is_empty())
+}
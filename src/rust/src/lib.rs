@@ -1,7 +1,452 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+mod aws;
+mod azure;
+mod cache;
+mod connection;
+mod encryption;
+mod envvars;
+mod logging;
+mod metrics;
+#[cfg(feature = "otel")]
+mod otel;
+mod prefix;
+mod rclone;
+mod stat_cache;
+mod throttle;
+mod transfer;
+mod uri;
+
+use azure::{AzureAuth, ServicePrincipal};
+use cache::{CacheLayer, DiskStore, MemoryStore};
+use encryption::{Algorithm, EncryptionLayer};
+use logging::{LogLevel, RLogger};
+use metrics::SessionMetrics;
+use prefix::PrefixLayer;
+use stat_cache::StatCacheLayer;
+use throttle::SplitWritesLayer;
+use transfer::PathFilter;
+
 use anyhow::Result;
+use base64::prelude::*;
 use extendr_api::prelude::*;
-use opendal::services::{Fs, Gcs};
-use opendal::{BlockingOperator, Metadata, Operator, OperatorInfo};
+use futures::StreamExt;
+use opendal::layers::observe::MetricsLayer;
+#[cfg(feature = "otel")]
+use opendal::layers::OtelTraceLayer;
+use opendal::layers::{
+    BlockingLayer, ConcurrentLimitLayer, ImmutableIndexLayer, LoggingLayer, MimeGuessLayer,
+    ThrottleLayer,
+};
+use opendal::raw::{Accessor, Layer, PresignedRequest};
+use opendal::services::{
+    Alluxio, Azdls, Cos, Dbfs, Dropbox, Fs, Ftp, Gcs, Gdrive, Github, Gridfs, Http, Huggingface,
+    Ipfs, Koofr, Lakefs, Memory, Mysql, Obs, Pcloud, Postgresql, Redis, S3, Seafile, Sqlite, Upyun,
+    VercelBlob, Webdav, Webhdfs, YandexDisk,
+};
+#[cfg(feature = "hdfs")]
+use opendal::services::Hdfs;
+#[cfg(feature = "rocksdb")]
+use opendal::services::Rocksdb;
+#[cfg(unix)]
+use opendal::services::Sftp;
+#[cfg(feature = "sled")]
+use opendal::services::Sled;
+use opendal::{
+    BlockingOperator, Buffer, Capability, Entry, ErrorKind, Lister, Metadata, Operator,
+    OperatorInfo, Scheme, StdReader, Writer,
+};
+
+/// How many requests vectorized operations keep in flight at once.
+const CONCURRENT_REQUESTS: usize = 16;
+
+/// Returns the tokio runtime shared by all operators.
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start tokio runtime")
+    })
+}
+
+/// Turns an operator into a `BlockingOperator`, adding a `BlockingLayer`
+/// for services that only implement the async API (S3, GCS, ...).
+fn into_blocking(op: Operator) -> Result<BlockingOperator> {
+    // Every service operator feeds the session metrics behind `metrics()`.
+    let op = op.layer(MetricsLayer::new(SessionMetrics));
+
+    if op.info().native_capability().blocking {
+        return Ok(op.blocking());
+    }
+
+    let _guard = runtime().enter();
+    Ok(op.layer(BlockingLayer::create()?).blocking())
+}
+
+/// Authenticates an Azure builder with a service principal.
+fn with_service_principal(builder: Azdls, sp: ServicePrincipal) -> Azdls {
+    let builder = builder
+        .tenant_id(&sp.tenant_id)
+        .client_id(&sp.client_id)
+        .client_secret(&sp.client_secret);
+
+    match sp.authority_host {
+        Some(host) => builder.authority_host(&host),
+        None => builder,
+    }
+}
+
+/// Flattens a named R list or character vector into the string key/value
+/// pairs opendal parses service configs from.
+fn option_pairs(options: &Robj) -> Result<Vec<(String, String)>> {
+    if options.is_null() || options.len() == 0 {
+        return Ok(Vec::new());
+    }
+
+    let names: Vec<String> = match options.names() {
+        Some(names) => names.map(str::to_string).collect(),
+        None => anyhow::bail!("options must be a named list or character vector"),
+    };
+
+    let values = if let Some(list) = options.as_list() {
+        list.values()
+            .map(|value| option_value(&value))
+            .collect::<Result<Vec<_>>>()?
+    } else if let Some(strings) = options.as_str_vector() {
+        strings.into_iter().map(str::to_string).collect()
+    } else {
+        anyhow::bail!("options must be a named list or character vector")
+    };
+
+    if names.iter().any(|name| name.is_empty()) {
+        anyhow::bail!("every option must be named");
+    }
+
+    Ok(names.into_iter().zip(values).collect())
+}
+
+/// Renders a length-one R value as an option string.
+fn option_value(value: &Robj) -> Result<String> {
+    if let Some(s) = value.as_str() {
+        Ok(s.to_string())
+    } else if let Some(b) = value.as_bool() {
+        Ok(b.to_string())
+    } else if let Some(i) = value.as_integer() {
+        Ok(i.to_string())
+    } else if let Some(x) = value.as_real() {
+        Ok(x.to_string())
+    } else {
+        anyhow::bail!("option values must be single strings, numbers or logicals")
+    }
+}
+
+/// Checks that an R number is a whole number in `1..=u32::MAX`.
+fn positive_u32(x: f64, name: &str) -> Result<u32> {
+    if x.fract() != 0.0 || x < 1.0 || x > u32::MAX as f64 {
+        anyhow::bail!("`{name}` must be a positive whole number below 2^32");
+    }
+    Ok(x as u32)
+}
+
+/// Checks that an R number is a whole number of bytes.
+fn whole_u64(x: f64, name: &str) -> Result<u64> {
+    if x.fract() != 0.0 || x < 0.0 || x > u64::MAX as f64 {
+        anyhow::bail!("`{name}` must be a non-negative whole number");
+    }
+    Ok(x as u64)
+}
+
+/// Converts R's seconds since the epoch, as in a POSIXct, to a timestamp.
+fn timestamp(secs: f64) -> Result<chrono::DateTime<chrono::Utc>> {
+    if !secs.is_finite() || secs < 0.0 {
+        anyhow::bail!("timestamps must be on or after 1970-01-01");
+    }
+    Ok(opendal::raw::parse_datetime_from_from_timestamp_millis((secs * 1000.0) as i64)?)
+}
+
+/// Checks the optional size cap in bytes and time-to-live in seconds of a
+/// cache.
+fn cache_limits(
+    max_bytes: Option<f64>,
+    ttl: Option<f64>,
+) -> Result<(Option<u64>, Option<Duration>)> {
+    if max_bytes.is_some_and(|b| !b.is_finite() || b < 0.0) {
+        anyhow::bail!("`max_bytes` must be a non-negative number");
+    }
+    let ttl = match ttl {
+        Some(t) => Some(
+            Duration::try_from_secs_f64(t)
+                .map_err(|_| anyhow::anyhow!("`ttl` must be a non-negative number of seconds"))?,
+        ),
+        None => None,
+    };
+
+    Ok((max_bytes.map(|b| b as u64), ttl))
+}
+
+/// Copies `content` chunk by chunk into a new R raw vector, so a download
+/// is never held twice: once as opendal's buffers and again as one
+/// contiguous `Vec`.
+fn raw_vector(content: Buffer) -> Robj {
+    let mut raw: Robj = Raw::new(content.len()).into();
+    if let Some(slice) = raw.as_raw_slice_mut() {
+        let mut pos = 0;
+        for chunk in content {
+            slice[pos..pos + chunk.len()].copy_from_slice(&chunk);
+            pos += chunk.len();
+        }
+    }
+    raw
+}
+
+/// The literal text every match of an anchored `regex` must start with,
+/// for narrowing a listing: none unless it starts with `^`, and none at
+/// all once it has alternatives.
+fn regex_prefix(regex: &str) -> String {
+    let Some(rest) = regex.strip_prefix('^') else {
+        return String::new();
+    };
+    if rest.contains('|') {
+        return String::new();
+    }
+
+    let mut prefix = String::new();
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        let literal = match c {
+            '\\' => match chars.next() {
+                Some(e) if e.is_ascii_punctuation() => e,
+                _ => break,
+            },
+            '.' | '+' | '*' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '^' | '$' => break,
+            c => c,
+        };
+        // A quantified character may not be there at all.
+        if matches!(chars.peek(), Some('?' | '*' | '{')) {
+            break;
+        }
+        prefix.push(literal);
+    }
+    prefix
+}
+
+/// Formats a byte count for people, e.g. `1.5 MiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// A directory in `tree()`'s view, with the files and subdirectories
+/// below it by name and their total size.
+#[derive(Default)]
+struct TreeDir {
+    dirs: BTreeMap<String, TreeDir>,
+    files: BTreeMap<String, u64>,
+    bytes: u64,
+}
+
+impl TreeDir {
+    fn insert(&mut self, relative: &str, size: u64) {
+        self.bytes += size;
+        match relative.split_once('/') {
+            Some((dir, rest)) => self.dirs.entry(dir.to_string()).or_default().insert(rest, size),
+            None => {
+                self.files.insert(relative.to_string(), size);
+            }
+        }
+    }
+
+    /// Renders the entries below this directory, subdirectories first,
+    /// indented by `depth`; subdirectories at `max_depth` aren't expanded.
+    fn render(&self, depth: usize, max_depth: usize, lines: &mut Vec<String>) {
+        let indent = "  ".repeat(depth);
+        for (name, dir) in &self.dirs {
+            lines.push(format!("{indent}{name}/ ({})", format_bytes(dir.bytes)));
+            if depth + 1 < max_depth {
+                dir.render(depth + 1, max_depth, lines);
+            }
+        }
+        for (name, size) in &self.files {
+            lines.push(format!("{indent}{name} ({})", format_bytes(*size)));
+        }
+    }
+}
+
+/// Builds a named character vector.
+fn named_strings(names: Vec<String>, values: Vec<String>) -> Result<Robj> {
+    let mut strings: Robj = values.into();
+    strings.set_names(names).map_err(|e| anyhow::anyhow!("{e}"))?;
+    Ok(strings)
+}
+
+/// Reads user metadata given as a named character vector.
+fn user_metadata(metadata: &Robj) -> Result<Vec<(String, String)>> {
+    if metadata.is_null() || metadata.len() == 0 {
+        return Ok(Vec::new());
+    }
+
+    let (Some(names), Some(values)) = (metadata.names(), metadata.as_str_vector()) else {
+        anyhow::bail!("`metadata` must be a named character vector");
+    };
+    let pairs: Vec<(String, String)> = names
+        .zip(values)
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    if pairs.iter().any(|(k, _)| k.is_empty()) {
+        anyhow::bail!("every `metadata` value must be named");
+    }
+    Ok(pairs)
+}
+
+/// Encodes UTF-8 text as `encoding`, via R's `iconv()` for anything other
+/// than UTF-8.
+fn encode_text(text: &str, encoding: &str) -> Result<Vec<u8>> {
+    if ["utf-8", "utf8"].contains(&encoding.to_ascii_lowercase().as_str()) {
+        return Ok(text.as_bytes().to_vec());
+    }
+
+    let encoded = call!("iconv", text, from = "UTF-8", to = encoding, toRaw = true)
+        .map_err(|e| anyhow::anyhow!("can't encode text as `{encoding}`: {e}"))?;
+    match encoded.as_list().and_then(|l| l.elt(0).ok()) {
+        Some(raw) if !raw.is_null() => Ok(raw.as_raw_slice().unwrap_or_default().to_vec()),
+        _ => anyhow::bail!("the text can't be represented in `{encoding}`"),
+    }
+}
+
+/// Seconds since the epoch, as R stores times.
+fn epoch_secs(t: chrono::DateTime<chrono::Utc>) -> f64 {
+    t.timestamp_millis() as f64 / 1000.0
+}
+
+/// Converts seconds since the epoch to a UTC POSIXct vector, `NA` where
+/// unknown.
+fn posixct(secs: Vec<Option<f64>>) -> Result<Robj> {
+    let mut times: Robj = secs.into();
+    times
+        .set_class(&["POSIXct", "POSIXt"])
+        .and_then(|t| t.set_attrib("tzone", "UTC"))
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    Ok(times)
+}
+
+/// Builds the data.frame `list_detail()` returns, one row per entry.
+fn entry_frame(entries: &[(String, String, Metadata)]) -> Result<Robj> {
+    let text = |f: fn(&Metadata) -> Option<&str>| -> Vec<Option<String>> {
+        entries.iter().map(|(_, _, m)| f(m).map(str::to_string)).collect()
+    };
+    let last_modified = entries
+        .iter()
+        .map(|(_, _, m)| m.last_modified().map(epoch_secs))
+        .collect();
+    let last_modified = posixct(last_modified)?;
+
+    Ok(data_frame!(
+        name = entries.iter().map(|(name, _, _)| name.as_str()).collect::<Vec<_>>(),
+        path = entries.iter().map(|(_, path, _)| path.as_str()).collect::<Vec<_>>(),
+        is_dir = entries.iter().map(|(_, _, m)| m.is_dir()).collect::<Vec<_>>(),
+        size = entries
+            .iter()
+            .map(|(_, _, m)| m.is_file().then(|| m.content_length() as f64))
+            .collect::<Vec<_>>(),
+        last_modified = last_modified,
+        etag = text(Metadata::etag),
+        content_type = text(Metadata::content_type),
+        version = text(Metadata::version),
+        stringsAsFactors = false
+    ))
+}
+
+/// Converts a capability to a named logical list, one entry per flag.
+fn capability_list(cap: Capability) -> Robj {
+    macro_rules! flags {
+        ($($flag:ident),* $(,)?) => {
+            List::from_names_and_values([$(stringify!($flag)),*], [$(cap.$flag),*])
+        };
+    }
+
+    flags!(
+        stat, stat_with_if_match, stat_with_if_none_match, stat_with_if_modified_since,
+        stat_with_if_unmodified_since, stat_with_override_cache_control,
+        stat_with_override_content_disposition, stat_with_override_content_type, stat_with_version,
+        stat_has_cache_control, stat_has_content_disposition, stat_has_content_length,
+        stat_has_content_range, stat_has_content_type, stat_has_content_encoding, stat_has_etag,
+        stat_has_last_modified, stat_has_version, stat_has_user_metadata, read, read_with_if_match,
+        read_with_if_none_match, read_with_if_modified_since, read_with_if_unmodified_since,
+        read_with_override_cache_control, read_with_override_content_disposition,
+        read_with_override_content_type, read_with_version, write, write_can_multi, write_can_empty,
+        write_can_append, write_with_content_type, write_with_content_disposition,
+        write_with_content_encoding, write_with_cache_control, write_with_if_match,
+        write_with_if_none_match, write_with_if_not_exists, write_with_user_metadata, create_dir,
+        delete, delete_with_version, copy, rename, list, list_with_limit, list_with_start_after,
+        list_with_recursive, list_with_versions, list_with_deleted,
+        list_has_cache_control, list_has_content_disposition, list_has_content_length,
+        list_has_content_range, list_has_content_type, list_has_etag, list_has_last_modified,
+        list_has_version, list_has_user_metadata, presign, presign_read, presign_stat,
+        presign_write, presign_delete, shared, blocking,
+    )
+    .map(Robj::from)
+    .unwrap_or_default()
+}
+
+/// Checks how long, in seconds, a presigned request stays valid.
+fn presign_expiry(expires_secs: f64) -> Result<Duration> {
+    if expires_secs.is_nan() || expires_secs <= 0.0 {
+        anyhow::bail!("`expires_secs` must be a positive number of seconds");
+    }
+    Duration::try_from_secs_f64(expires_secs)
+        .map_err(|_| anyhow::anyhow!("`expires_secs` is too large"))
+}
+
+/// Converts a presigned request to a list of `method`, `url`, the `headers`
+/// the request must be sent with (a named character vector) and the
+/// `expires_at` POSIXct, so it can be replayed as is with curl or httr2.
+/// Each of `required` is added unless the service already signed it.
+fn presigned(req: PresignedRequest, expire: Duration, required: &[(&str, &str)]) -> Result<Robj> {
+    let expires_at = (SystemTime::now() + expire)
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64());
+    let expires_at = posixct(vec![Some(expires_at)])?;
+
+    let (mut names, mut values): (Vec<String>, Vec<String>) = req
+        .header()
+        .iter()
+        .map(|(k, v)| {
+            let v = String::from_utf8_lossy(v.as_bytes()).into_owned();
+            (k.as_str().to_string(), v)
+        })
+        .unzip();
+    for (name, value) in required {
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            names.push(name.to_string());
+            values.push(value.to_string());
+        }
+    }
+    let headers = named_strings(names, values)?;
+
+    Ok(list!(
+        method = req.method().as_str(),
+        url = req.uri().to_string(),
+        headers = headers,
+        expires_at = expires_at
+    )
+    .into())
+}
 
 /// Represents metadata for an entry in OpenDAL.
 #[derive(Debug, Clone)]
@@ -46,9 +491,14 @@ impl OpenDALMetadata {
     //     self.meta.content_range()
     // }
 
-    // pub fn last_modified(&self) -> Robj {
-    //     self.meta.last_modified()
-    // }
+    /// When the object last changed, as a UTC POSIXct, or `NULL` if the
+    /// service doesn't say.
+    pub fn last_modified(&self) -> Result<Robj> {
+        match self.meta.last_modified() {
+            Some(t) => posixct(vec![Some(epoch_secs(t))]),
+            None => Ok(().into()),
+        }
+    }
 
     pub fn etag(&self) -> Option<&str> {
         self.meta.etag()
@@ -61,11 +511,45 @@ impl OpenDALMetadata {
     pub fn version(&self) -> Option<&str> {
         self.meta.version()
     }
+
+    /// User metadata stored with the object as a named character vector,
+    /// or `NULL` if there is none.
+    pub fn user_metadata(&self) -> Result<Robj> {
+        let Some(metadata) = self.meta.user_metadata() else {
+            return Ok(().into());
+        };
+        let mut pairs: Vec<_> = metadata.iter().collect();
+        pairs.sort();
+        let (names, values) = pairs.into_iter().map(|(k, v)| (k.clone(), v.clone())).unzip();
+        named_strings(names, values)
+    }
 }
 
 #[extendr]
 struct OpenDALOperator {
-    op: BlockingOperator
+    op: BlockingOperator,
+    sizes: IoSizes,
+}
+
+/// Request sizes for reads and writes made without their own.
+#[derive(Debug, Clone, Copy, Default)]
+struct IoSizes {
+    /// Reads are fetched in ranges of this many bytes; by default, in one
+    /// streaming request.
+    read_buffer: Option<usize>,
+    /// Uploads are sent in parts of this many bytes where the service takes
+    /// parts; by default, 8 MiB for `write()` and file uploads, and the
+    /// service's own choice for streaming writers.
+    write_chunk: Option<usize>,
+}
+
+impl From<BlockingOperator> for OpenDALOperator {
+    fn from(op: BlockingOperator) -> Self {
+        OpenDALOperator {
+            op,
+            sizes: IoSizes::default(),
+        }
+    }
 }
 
 #[extendr]
@@ -93,13 +577,168 @@ impl OpenDALOperatorInfo {
         self.info.name()
     }
 
-    // pub fn full_capability(&self) -> Capability {
-    //     self.info.full_capability()
-    // }
+    /// What the operator supports, including what opendal emulates on top
+    /// of the service (e.g. recursive listing), as a named logical list.
+    pub fn full_capability(&self) -> Robj {
+        capability_list(self.info.full_capability())
+    }
 
-    // pub fn native_capability(&self) -> Capability {
-    //     self.info.native_capability()
-    // }
+    /// What the service itself supports, as a named logical list.
+    pub fn native_capability(&self) -> Robj {
+        capability_list(self.info.native_capability())
+    }
+}
+
+/// Reads an object incrementally, so large files needn't fit in memory.
+#[extendr]
+struct OpenDALReader {
+    path: String,
+    reader: Option<StdReader>,
+}
+
+impl OpenDALReader {
+    fn open(&mut self) -> Result<&mut StdReader> {
+        match self.reader.as_mut() {
+            Some(reader) => Ok(reader),
+            None => anyhow::bail!("reader for `{}` is closed", self.path),
+        }
+    }
+}
+
+#[extendr]
+impl OpenDALReader {
+    /// Reads up to `n` bytes from the current position; fewer only at the
+    /// end of the object, where an empty raw vector is returned.
+    pub fn read(&mut self, n: f64) -> Result<Robj> {
+        let n = whole_u64(n, "n")?;
+        let mut buf = Vec::new();
+        self.open()?.take(n).read_to_end(&mut buf)?;
+        Ok(Raw::from_bytes(&buf).into())
+    }
+
+    /// Moves to byte `pos` from the start and returns it.
+    pub fn seek(&mut self, pos: f64) -> Result<f64> {
+        let pos = whole_u64(pos, "pos")?;
+        Ok(self.open()?.seek(SeekFrom::Start(pos))? as f64)
+    }
+
+    /// Current position, in bytes from the start.
+    pub fn tell(&mut self) -> Result<f64> {
+        Ok(self.open()?.stream_position()? as f64)
+    }
+
+    pub fn close(&mut self) {
+        self.reader = None;
+    }
+}
+
+/// Streams data written from R into a single object, committed when the
+/// writer is closed.
+#[extendr]
+struct OpenDALWriter {
+    path: String,
+    writer: Option<Writer>,
+}
+
+impl OpenDALWriter {
+    fn open(&mut self) -> Result<&mut Writer> {
+        match self.writer.as_mut() {
+            Some(writer) => Ok(writer),
+            None => anyhow::bail!("writer for `{}` is closed", self.path),
+        }
+    }
+}
+
+#[extendr]
+impl OpenDALWriter {
+    pub fn write(&mut self, data: Vec<u8>) -> Result<()> {
+        let writer = self.open()?;
+        Ok(runtime().block_on(writer.write(data))?)
+    }
+
+    /// Finishes the upload and makes the object visible.
+    pub fn close(&mut self) -> Result<()> {
+        let closed = runtime().block_on(self.open()?.close());
+        self.writer = None;
+        closed?;
+        Ok(())
+    }
+
+    /// Gives up on the upload, cleaning up any parts already sent. Not every
+    /// service can abort, e.g. `fs` without an atomic write directory.
+    pub fn abort(&mut self) -> Result<()> {
+        let aborted = runtime().block_on(self.open()?.abort());
+        self.writer = None;
+        Ok(aborted?)
+    }
+}
+
+/// Walks a listing a page at a time, so prefixes with millions of entries
+/// needn't be held in memory at once.
+#[extendr]
+struct OpenDALLister {
+    lister: Lister,
+    peeked: Option<opendal::Result<Entry>>,
+    start_after: Option<String>,
+    remaining: Option<u64>,
+}
+
+impl OpenDALLister {
+    fn next_entry(&mut self) -> Option<opendal::Result<Entry>> {
+        if let Some(entry) = self.peeked.take() {
+            return Some(entry);
+        }
+        if self.remaining == Some(0) {
+            return None;
+        }
+
+        loop {
+            let entry = runtime().block_on(self.lister.next())?;
+            // Not every service can start after a key, so filter here too.
+            if let (Ok(e), Some(after)) = (&entry, &self.start_after) {
+                if e.path() <= after.as_str() {
+                    continue;
+                }
+            }
+            if let Some(n) = self.remaining.as_mut() {
+                *n -= 1;
+            }
+            return Some(entry);
+        }
+    }
+
+    /// Drains the rest of the listing.
+    fn entries(&mut self) -> Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        while let Some(entry) = self.next_entry() {
+            entries.push(entry?);
+        }
+        Ok(entries)
+    }
+}
+
+#[extendr]
+impl OpenDALLister {
+    /// Returns the paths of up to the next `n` entries, fewer only once
+    /// the listing runs out.
+    pub fn next_page(&mut self, #[default = "1000"] n: f64) -> Result<Vec<String>> {
+        let n = whole_u64(n, "n")?;
+        let mut paths = Vec::new();
+        while (paths.len() as u64) < n {
+            match self.next_entry() {
+                Some(entry) => paths.push(entry?.path().to_string()),
+                None => break,
+            }
+        }
+        Ok(paths)
+    }
+
+    pub fn has_next(&mut self) -> bool {
+        if self.peeked.is_none() {
+            self.peeked = self.next_entry();
+        }
+        self.peeked.is_some()
+    }
 }
 
 #[extendr]
@@ -107,53 +746,140 @@ impl OpenDALOperator {
     pub fn new_fs(root_path: String) -> Result<Self> {
         let builder = Fs::default().root(&root_path);
 
-        let operator = Operator::new(builder)?.finish().blocking();
-
-        Ok(Self { op: operator })
-    }
-
-    // fn new_s3(
-    //     bucket: String,
-    //     region: Option<String>,
-    //     endpoint: Option<String>,
-    //     access_key_id: Option<String>,
-    //     secret_access_key: Option<String>,
-    //     session_token: Option<String>,
-    //     enable_virtual_host_style: Option<bool>,
-    //     root: Option<String>,
-    // ) -> Result<Self> {
-    //     let mut builder = S3::default();
-    //     builder.bucket(&bucket);
-
-    //     if let Some(r) = region {
-    //         builder.region(&r);
-    //     }
-    //     if let Some(e) = endpoint {
-    //         builder.endpoint(&e);
-    //     }
-    //     if let Some(ak) = access_key_id {
-    //         builder.access_key_id(&ak);
-    //     }
-    //     if let Some(sk) = secret_access_key {
-    //         builder.secret_access_key(&sk);
-    //     }
-    //     if let Some(st) = session_token {
-    //         builder.security_token(&st);
-    //     }
-    //     if let Some(vhost) = enable_virtual_host_style {
-    //         if vhost {
-    //             builder.enable_virtual_host_style();
-    //         }
-    //     }
-    //     if let Some(p_root) = root {
-    //         builder.root(&p_root);
-    //     }
-
-    //     let operator_builder = Operator::new(builder)?;
-    //     let operator = operator_builder.finish();
-    //     Ok(Self { op: operator.blocking() })
-    // }
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_memory() -> Result<Self> {
+        let builder = Memory::default();
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+
+        Ok(Self::from(operator))
+    }
+
+    /// Connects to an S3 bucket. `profile` reads static keys or a role from
+    /// the shared AWS config files; profiles that sign in through SSO,
+    /// `credential_process` or a web identity token aren't supported and
+    /// are refused.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_s3(
+        bucket: String,
+        region: Option<String>,
+        endpoint: Option<String>,
+        access_key_id: Option<String>,
+        secret_access_key: Option<String>,
+        session_token: Option<String>,
+        enable_virtual_host_style: Option<bool>,
+        root: Option<String>,
+        profile: Option<String>,
+        role_arn: Option<String>,
+        external_id: Option<String>,
+        role_session_name: Option<String>,
+    ) -> Result<Self> {
+        // Explicit arguments win over `profile`, which wins over the default
+        // chain: AWS_* env vars, the default profile, web identity and IMDS.
+        let profile = match profile {
+            Some(p) => aws::load_profile(&p)?,
+            None => aws::AwsProfile::default(),
+        };
+        let region = region.or(profile.region);
+        let access_key_id = access_key_id.or(profile.access_key_id);
+        let secret_access_key = secret_access_key.or(profile.secret_access_key);
+        let session_token = session_token.or(profile.session_token);
+        let role_arn = role_arn.or(profile.role_arn);
+        let external_id = external_id.or(profile.external_id);
+        let role_session_name = role_session_name.or(profile.role_session_name);
+
+        let mut builder = S3::default()
+            .bucket(&bucket);
+
+        // The base credentials above are exchanged for the role through STS.
+        if let Some(arn) = role_arn {
+            builder = builder.role_arn(&arn);
+        }
+
+        if let Some(id) = external_id {
+            builder = builder.external_id(&id);
+        }
+
+        if let Some(name) = role_session_name {
+            builder = builder.role_session_name(&name);
+        }
+
+        if let Some(r) = region {
+            builder = builder.region(&r);
+        }
+
+        if let Some(ep) = endpoint {
+            builder = builder.endpoint(&ep);
+        }
+
+        if let Some(ak) = access_key_id {
+            builder = builder.access_key_id(&ak);
+        }
 
+        if let Some(sk) = secret_access_key {
+            builder = builder.secret_access_key(&sk);
+        }
+
+        if let Some(st) = session_token {
+            builder = builder.session_token(&st);
+        }
+
+        if enable_virtual_host_style == Some(true) {
+            builder = builder.enable_virtual_host_style();
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    /// Connects to a MinIO server using path-style addressing. `endpoint` may
+    /// omit the scheme, in which case `secure` picks https (default) or http.
+    pub fn new_minio(
+        endpoint: String,
+        bucket: String,
+        access_key: String,
+        secret_key: String,
+        secure: Option<bool>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let endpoint = if endpoint.contains("://") {
+            endpoint
+        } else if secure == Some(false) {
+            format!("http://{endpoint}")
+        } else {
+            format!("https://{endpoint}")
+        };
+
+        // MinIO ignores the region but request signing needs one.
+        let mut builder = S3::default()
+            .endpoint(&endpoint)
+            .bucket(&bucket)
+            .region("us-east-1")
+            .access_key_id(&access_key)
+            .secret_access_key(&secret_key)
+            .disable_config_load();
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    /// Connects to Google Cloud Storage. Without `credential_path` or
+    /// `credential_json_content`, Application Default Credentials are used:
+    /// `GOOGLE_APPLICATION_CREDENTIALS`, the gcloud ADC file, then the
+    /// metadata server (GCE, GKE workload identity) as `service_account`.
+    #[allow(clippy::too_many_arguments)]
     pub fn new_gcs(
         bucket: String,
         credential_path: Option<String>,
@@ -162,6 +888,7 @@ impl OpenDALOperator {
         default_storage_class: Option<String>,
         predefined_acl: Option<String>,
         root: Option<String>,
+        service_account: Option<String>,
     ) -> Result<Self> {
         let mut builder = Gcs::default()
             .bucket(&bucket);
@@ -169,8 +896,18 @@ impl OpenDALOperator {
         if let Some(cp) = credential_path {
             builder = builder.credential_path(&cp);
         } else if let Some(cc_json) = credential_json_content {
+            // opendal expects the key file base64 encoded, R users have the JSON.
+            let cc_json = if cc_json.trim_start().starts_with('{') {
+                BASE64_STANDARD.encode(cc_json)
+            } else {
+                cc_json
+            };
             builder = builder.credential(&cc_json);
         }
+
+        if let Some(sa) = service_account {
+            builder = builder.service_account(&sa);
+        }
     
         if let Some(ep) = endpoint {
             builder = builder.endpoint(&ep);
@@ -188,71 +925,2352 @@ impl OpenDALOperator {
             builder = builder.root(&r);
         }
 
-        let operator = Operator::new(builder)?.finish().blocking();
-        Ok(Self { op: operator })
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
     }
 
-    pub fn info(&self) -> OpenDALOperatorInfo {
-        let info = self.op.info();
-        OpenDALOperatorInfo::from(info)
-    }
+    /// Connects to an ADLS Gen2 filesystem. `auth` is one of "auto" (default),
+    /// "key", "sas", "env" (service principal from `AZURE_*` variables) or
+    /// "managed_identity". "cli" is refused, as opendal can't take a bearer
+    /// token from the Azure CLI.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_azdls(
+        filesystem: String,
+        endpoint: String,
+        account_name: Option<String>,
+        account_key: Option<String>,
+        sas_token: Option<String>,
+        root: Option<String>,
+        auth: Option<String>,
+    ) -> Result<Self> {
+        let auth = match auth {
+            Some(a) => AzureAuth::from_str(&a)?,
+            None => AzureAuth::Auto,
+        };
 
-    // General Paths
-    pub fn exists(&self, path: &str) -> Result<bool> {
-        Ok(self.op.exists(path)?)
-    }
+        let mut builder = Azdls::default()
+            .filesystem(&filesystem)
+            .endpoint(&endpoint);
 
-    /// Retrieves metadata for a path.
-    pub fn stat(&self, path: &str) -> Result<OpenDALMetadata> {
-        let meta = self.op.stat(path)?;
-        Ok(OpenDALMetadata::from(meta))
-    }
+        if let Some(an) = account_name {
+            builder = builder.account_name(&an);
+        }
 
-    // Directories
-    pub fn create_dir(&self, path: &str) -> Result<()> {
-        Ok(self.op.create_dir(path)?)
-    }
+        match auth {
+            AzureAuth::Key => match account_key {
+                Some(ak) => builder = builder.account_key(&ak),
+                None => anyhow::bail!("`auth = \"key\"` requires `account_key`"),
+            },
+            AzureAuth::Sas => match sas_token {
+                Some(sas) => builder = builder.sas_token(&sas),
+                None => anyhow::bail!("`auth = \"sas\"` requires `sas_token`"),
+            },
+            AzureAuth::Env => match ServicePrincipal::from_env() {
+                Some(sp) => builder = with_service_principal(builder, sp),
+                None => anyhow::bail!(
+                    "`auth = \"env\"` requires AZURE_TENANT_ID, AZURE_CLIENT_ID and AZURE_CLIENT_SECRET"
+                ),
+            },
+            // Nothing to configure: opendal falls back to the metadata service.
+            AzureAuth::ManagedIdentity => {}
+            AzureAuth::Auto => {
+                if let Some(ak) = account_key {
+                    builder = builder.account_key(&ak);
+                }
 
-    pub fn list(&self, path: &str) -> Result<Vec<String>> {
-        let entries = self.op.list(path)?;
-        Ok(entries
-            .into_iter()
-            .map(|entry| entry.name().to_string())
-            .collect())
-    }
+                if let Some(sas) = sas_token {
+                    builder = builder.sas_token(&sas);
+                }
 
-    // Files
-    pub fn read_raw(&self, path: &str) -> Result<Robj> {
-        let content = self.op.read(path)?;
-        Ok(Raw::from_bytes(&content.to_vec()).into())
-    }
+                if let Some(sp) = ServicePrincipal::from_env() {
+                    builder = with_service_principal(builder, sp);
+                }
+            }
+        }
 
-    pub fn write(&self, path: &str, data: Vec<u8>) -> Result<()> {
-        let _ = self.op.write(path, data)?;
-        Ok(())
-    }
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
 
-    pub fn delete(&self, path: &str) -> Result<()> {
-        Ok(self.op.delete(path)?)
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
     }
 
-    pub fn copy(&self, source_path: &str, destination_path: &str) -> Result<()> {
-        Ok(self.op.copy(source_path, destination_path)?)
-    }
+    pub fn new_http(
+        endpoint: String,
+        root: Option<String>,
+        username: Option<String>,
+        password: Option<String>,
+        token: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Http::default()
+            .endpoint(&endpoint);
 
-    pub fn rename(&self, old_path: &str, new_path: &str) -> Result<()> {
-        Ok(self.op.rename(old_path, new_path)?)
-    }
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
 
-    pub fn remove_all(&self, path: &str) -> Result<()> {
+        if let Some(u) = username {
+            builder = builder.username(&u);
+        }
+
+        if let Some(p) = password {
+            builder = builder.password(&p);
+        }
+
+        if let Some(t) = token {
+            builder = builder.token(&t);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_webdav(
+        endpoint: String,
+        username: Option<String>,
+        password: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Webdav::default()
+            .endpoint(&endpoint);
+
+        if let Some(u) = username {
+            builder = builder.username(&u);
+        }
+
+        if let Some(p) = password {
+            builder = builder.password(&p);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_sftp(
+        endpoint: String,
+        user: Option<String>,
+        key_path: Option<String>,
+        key_passphrase: Option<String>,
+        known_hosts_strategy: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        #[cfg(unix)]
+        {
+            if key_passphrase.is_some() {
+                anyhow::bail!(
+                    "sftp does not accept key passphrases; add the key to ssh-agent instead"
+                );
+            }
+
+            let mut builder = Sftp::default()
+                .endpoint(&endpoint);
+
+            if let Some(u) = user {
+                builder = builder.user(&u);
+            }
+
+            if let Some(kp) = key_path {
+                builder = builder.key(&kp);
+            }
+
+            if let Some(khs) = known_hosts_strategy {
+                builder = builder.known_hosts_strategy(&khs);
+            }
+
+            if let Some(r) = root {
+                builder = builder.root(&r);
+            }
+
+            let operator = into_blocking(Operator::new(builder)?.finish())?;
+            Ok(Self::from(operator))
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = (endpoint, user, key_path, key_passphrase, known_hosts_strategy, root);
+            anyhow::bail!("the sftp service is only available on unix platforms")
+        }
+    }
+
+    /// Connects to an FTP server; use an `ftps://` endpoint to enable TLS.
+    pub fn new_ftp(
+        endpoint: String,
+        user: Option<String>,
+        password: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Ftp::default()
+            .endpoint(&endpoint);
+
+        if let Some(u) = user {
+            builder = builder.user(&u);
+        }
+
+        if let Some(p) = password {
+            builder = builder.password(&p);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_webhdfs(
+        endpoint: String,
+        delegation_token: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Webhdfs::default()
+            .endpoint(&endpoint);
+
+        if let Some(dt) = delegation_token {
+            builder = builder.delegation(&dt);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    /// Connects to HDFS through libhdfs; requires the `hdfs` cargo feature.
+    pub fn new_hdfs(
+        name_node: String,
+        root: Option<String>,
+        user: Option<String>,
+    ) -> Result<Self> {
+        #[cfg(feature = "hdfs")]
+        {
+            let mut builder = Hdfs::default()
+                .name_node(&name_node);
+
+            if let Some(r) = root {
+                builder = builder.root(&r);
+            }
+
+            if let Some(u) = user {
+                builder = builder.user(&u);
+            }
+
+            let operator = into_blocking(Operator::new(builder)?.finish())?;
+            Ok(Self::from(operator))
+        }
+
+        #[cfg(not(feature = "hdfs"))]
+        {
+            let _ = (name_node, root, user);
+            anyhow::bail!("opendalr was built without the `hdfs` feature")
+        }
+    }
+
+    pub fn new_cos(
+        bucket: String,
+        endpoint: String,
+        secret_id: Option<String>,
+        secret_key: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Cos::default()
+            .bucket(&bucket)
+            .endpoint(&endpoint);
+
+        if let Some(si) = secret_id {
+            builder = builder.secret_id(&si);
+        }
+
+        if let Some(sk) = secret_key {
+            builder = builder.secret_key(&sk);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_obs(
+        bucket: String,
+        endpoint: String,
+        access_key_id: Option<String>,
+        secret_access_key: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Obs::default()
+            .bucket(&bucket)
+            .endpoint(&endpoint);
+
+        if let Some(aki) = access_key_id {
+            builder = builder.access_key_id(&aki);
+        }
+
+        if let Some(sak) = secret_access_key {
+            builder = builder.secret_access_key(&sak);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    /// Connects to Dropbox; with `refresh_token`, `client_id` and `client_secret`
+    /// set, expired access tokens are refreshed automatically.
+    pub fn new_dropbox(
+        access_token: Option<String>,
+        refresh_token: Option<String>,
+        client_id: Option<String>,
+        client_secret: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Dropbox::default();
+
+        if let Some(at) = access_token {
+            builder = builder.access_token(&at);
+        }
+
+        if let Some(rt) = refresh_token {
+            builder = builder.refresh_token(&rt);
+        }
+
+        if let Some(ci) = client_id {
+            builder = builder.client_id(&ci);
+        }
+
+        if let Some(cs) = client_secret {
+            builder = builder.client_secret(&cs);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_gdrive(
+        access_token: Option<String>,
+        refresh_token: Option<String>,
+        client_id: Option<String>,
+        client_secret: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Gdrive::default();
+
+        if let Some(at) = access_token {
+            builder = builder.access_token(&at);
+        }
+
+        if let Some(rt) = refresh_token {
+            builder = builder.refresh_token(&rt);
+        }
+
+        if let Some(ci) = client_id {
+            builder = builder.client_id(&ci);
+        }
+
+        if let Some(cs) = client_secret {
+            builder = builder.client_secret(&cs);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_huggingface(
+        repo_type: Option<String>,
+        repo_id: String,
+        revision: Option<String>,
+        token: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Huggingface::default()
+            .repo_id(&repo_id);
+
+        if let Some(rt) = repo_type {
+            builder = builder.repo_type(&rt);
+        }
+
+        if let Some(rev) = revision {
+            builder = builder.revision(&rev);
+        }
+
+        if let Some(t) = token {
+            builder = builder.token(&t);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    /// Reads repository contents through the GitHub API, on the default
+    /// branch. opendal's GitHub service has no way to pass the API's `ref`
+    /// parameter, so selecting another branch, tag or commit with `git_ref`
+    /// is an error rather than silently reading the default branch.
+    pub fn new_github(
+        owner: String,
+        repo: String,
+        git_ref: Option<String>,
+        token: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        if let Some(r) = git_ref {
+            anyhow::bail!(
+                "can't read ref `{r}`: opendal's github service only reads the default branch"
+            );
+        }
+
+        let mut builder = Github::default()
+            .owner(&owner)
+            .repo(&repo);
+
+        if let Some(t) = token {
+            builder = builder.token(&t);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_ipfs(
+        endpoint: String,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Ipfs::default()
+            .endpoint(&endpoint);
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_dbfs(
+        endpoint: String,
+        token: String,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Dbfs::default()
+            .endpoint(&endpoint)
+            .token(&token);
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_alluxio(
+        endpoint: String,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Alluxio::default()
+            .endpoint(&endpoint);
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_lakefs(
+        endpoint: String,
+        repository: String,
+        branch: Option<String>,
+        username: String,
+        password: String,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Lakefs::default()
+            .endpoint(&endpoint)
+            .repository(&repository)
+            .username(&username)
+            .password(&password);
+
+        if let Some(b) = branch {
+            builder = builder.branch(&b);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_vercel_blob(
+        token: String,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = VercelBlob::default()
+            .token(&token);
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_upyun(
+        bucket: String,
+        operator: String,
+        password: String,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Upyun::default()
+            .bucket(&bucket)
+            .operator(&operator)
+            .password(&password);
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_koofr(
+        endpoint: String,
+        email: String,
+        password: String,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Koofr::default()
+            .endpoint(&endpoint)
+            .email(&email)
+            .password(&password);
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_pcloud(
+        endpoint: String,
+        username: String,
+        password: String,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Pcloud::default()
+            .endpoint(&endpoint)
+            .username(&username)
+            .password(&password);
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_seafile(
+        endpoint: String,
+        repo_name: String,
+        username: String,
+        password: String,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Seafile::default()
+            .endpoint(&endpoint)
+            .repo_name(&repo_name)
+            .username(&username)
+            .password(&password);
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_yandex_disk(
+        access_token: String,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = YandexDisk::default()
+            .access_token(&access_token);
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_redis(
+        endpoint: String,
+        username: Option<String>,
+        password: Option<String>,
+        db: Option<i32>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Redis::default()
+            .endpoint(&endpoint);
+
+        if let Some(u) = username {
+            builder = builder.username(&u);
+        }
+
+        if let Some(p) = password {
+            builder = builder.password(&p);
+        }
+
+        if let Some(db) = db {
+            builder = builder.db(i64::from(db));
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_sqlite(
+        connection_string: String,
+        table: String,
+        key_field: Option<String>,
+        value_field: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Sqlite::default()
+            .connection_string(&connection_string)
+            .table(&table);
+
+        if let Some(kf) = key_field {
+            builder = builder.key_field(&kf);
+        }
+
+        if let Some(vf) = value_field {
+            builder = builder.value_field(&vf);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_postgresql(
+        connection_string: String,
+        table: String,
+        key_field: Option<String>,
+        value_field: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Postgresql::default()
+            .connection_string(&connection_string)
+            .table(&table);
+
+        if let Some(kf) = key_field {
+            builder = builder.key_field(&kf);
+        }
+
+        if let Some(vf) = value_field {
+            builder = builder.value_field(&vf);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_mysql(
+        connection_string: String,
+        table: String,
+        key_field: Option<String>,
+        value_field: Option<String>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Mysql::default()
+            .connection_string(&connection_string)
+            .table(&table);
+
+        if let Some(kf) = key_field {
+            builder = builder.key_field(&kf);
+        }
+
+        if let Some(vf) = value_field {
+            builder = builder.value_field(&vf);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn new_gridfs(
+        connection_string: String,
+        database: String,
+        bucket: Option<String>,
+        chunk_size: Option<i32>,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let mut builder = Gridfs::default()
+            .connection_string(&connection_string)
+            .database(&database);
+
+        if let Some(b) = bucket {
+            builder = builder.bucket(&b);
+        }
+
+        if let Some(cs) = chunk_size {
+            builder = builder.chunk_size(u32::try_from(cs)?);
+        }
+
+        if let Some(r) = root {
+            builder = builder.root(&r);
+        }
+
+        let operator = into_blocking(Operator::new(builder)?.finish())?;
+        Ok(Self::from(operator))
+    }
+
+    /// Opens an embedded RocksDB store; requires the `rocksdb` cargo feature.
+    pub fn new_rocksdb(datadir: String, root: Option<String>) -> Result<Self> {
+        #[cfg(feature = "rocksdb")]
+        {
+            let mut builder = Rocksdb::default()
+                .datadir(&datadir);
+
+            if let Some(r) = root {
+                builder = builder.root(&r);
+            }
+
+            let operator = into_blocking(Operator::new(builder)?.finish())?;
+            Ok(Self::from(operator))
+        }
+
+        #[cfg(not(feature = "rocksdb"))]
+        {
+            let _ = (datadir, root);
+            anyhow::bail!("opendalr was built without the `rocksdb` feature")
+        }
+    }
+
+    /// Opens an embedded sled store; requires the `sled` cargo feature.
+    pub fn new_sled(datadir: String, root: Option<String>) -> Result<Self> {
+        #[cfg(feature = "sled")]
+        {
+            let mut builder = Sled::default()
+                .datadir(&datadir);
+
+            if let Some(r) = root {
+                builder = builder.root(&r);
+            }
+
+            let operator = into_blocking(Operator::new(builder)?.finish())?;
+            Ok(Self::from(operator))
+        }
+
+        #[cfg(not(feature = "sled"))]
+        {
+            let _ = (datadir, root);
+            anyhow::bail!("opendalr was built without the `sled` feature")
+        }
+    }
+
+    /// Builds an operator for any compiled-in service from a named list of
+    /// options, using the same keys as opendal's service configs.
+    pub fn new_service(scheme: &str, options: Robj) -> Result<Self> {
+        let scheme = Scheme::from_str(scheme)?;
+        let options = option_pairs(&options)?;
+
+        let operator = into_blocking(Operator::via_iter(scheme, options)?)?;
+        Ok(Self::from(operator))
+    }
+
+    /// Builds an operator from a connection string such as
+    /// `s3://bucket/prefix?region=us-east-1`.
+    pub fn from_uri(uri: &str) -> Result<Self> {
+        let (scheme, options) = uri::parse_uri(uri)?;
+
+        let operator = into_blocking(Operator::via_iter(scheme, options)?)?;
+        Ok(Self::from(operator))
+    }
+
+    /// Reuses a remote from an rclone config file. `root` is the path that
+    /// would follow `remote:` on the rclone command line, starting with the
+    /// bucket for S3 and GCS remotes. Obscured passwords are revealed the
+    /// same way rclone does.
+    pub fn from_rclone(
+        config_path: Option<String>,
+        remote_name: &str,
+        root: Option<String>,
+    ) -> Result<Self> {
+        let root = root.unwrap_or_default();
+        let (scheme, options) =
+            rclone::load_remote(config_path.as_deref(), remote_name, &root)?;
+
+        let operator = into_blocking(Operator::via_iter(scheme, options)?)?;
+        Ok(Self::from(operator))
+    }
+
+    /// Configures `scheme` from the service's conventional environment
+    /// variables (`AWS_*`, `AZURE_STORAGE_*`, `GOOGLE_APPLICATION_CREDENTIALS`,
+    /// ...) plus any `OPENDAL_<SCHEME>_<OPTION>` overrides, so secrets never
+    /// pass through R. For bucket based services without a configured
+    /// bucket, `root` starts with the bucket name.
+    pub fn from_env(scheme: &str, root: Option<String>) -> Result<Self> {
+        let scheme = Scheme::from_str(scheme)?;
+        let options = envvars::load_env(scheme, root.as_deref());
+
+        let operator = into_blocking(Operator::via_iter(scheme, options)?)?;
+        Ok(Self::from(operator))
+    }
+
+    pub fn info(&self) -> OpenDALOperatorInfo {
+        let info = self.op.info();
+        OpenDALOperatorInfo::from(info)
+    }
+
+    /// Verifies up front that the service is reachable with these
+    /// credentials by listing the root, failing with a hint at the likely
+    /// cause otherwise.
+    pub fn check(&self) -> Result<()> {
+        self.op.check().map_err(|e| {
+            let hint = match e.kind() {
+                ErrorKind::PermissionDenied => "check the credentials and their permissions",
+                ErrorKind::NotFound => "check that the bucket and root exist",
+                ErrorKind::ConfigInvalid => "check the connection options",
+                _ => "check the endpoint and network access",
+            };
+            let info = self.op.info();
+            anyhow::anyhow!(
+                "can't reach the `{}` service at `{}`; {hint}: {e}",
+                info.scheme(),
+                info.root()
+            )
+        })
+    }
+
+    /// Returns an operator rooted at `subpath` below this one's root. It
+    /// shares this operator's client and credentials, so nothing is
+    /// re-authenticated.
+    pub fn with_root(&self, subpath: &str) -> Result<Self> {
+        self.layer(PrefixLayer::new(subpath))
+    }
+
+    /// Same as `with_root`, for handing a sub-prefix to a pipeline step.
+    pub fn child(&self, prefix: &str) -> Result<Self> {
+        self.with_root(prefix)
+    }
+
+    /// Returns an operator that reports each storage operation at `level`
+    /// ("debug", "info", "warn" or "error") or above. Records go to the
+    /// function in `options(opendalr.log_handler)`, or to `message()`.
+    pub fn with_logging(&self, #[default = "NULL"] level: Option<String>) -> Result<Self> {
+        let level = match level {
+            Some(l) => LogLevel::from_str(&l)?,
+            None => LogLevel::Info,
+        };
+
+        self.layer(LoggingLayer::new(RLogger::new(level)))
+    }
+
+    /// Returns an operator that allows at most `n` operations in flight at
+    /// once, optionally with a separate cap on concurrent HTTP requests.
+    /// Blocking calls fail instead of waiting once every permit is held,
+    /// e.g. by open readers or writers.
+    pub fn with_concurrency_limit(
+        &self,
+        n: i32,
+        #[default = "NULL"] http_limit: Option<i32>,
+    ) -> Result<Self> {
+        let permits = usize::try_from(n).ok().filter(|&n| n > 0);
+        let Some(permits) = permits else {
+            anyhow::bail!("`n` must be a positive integer");
+        };
+
+        let mut layer = ConcurrentLimitLayer::new(permits);
+        if let Some(limit) = http_limit {
+            let Some(limit) = usize::try_from(limit).ok().filter(|&n| n > 0) else {
+                anyhow::bail!("`http_limit` must be a positive integer");
+            };
+            layer = layer.with_http_concurrent_limit(limit);
+        }
+
+        self.layer(layer)
+    }
+
+    /// Returns an operator whose reads are fetched in ranges of
+    /// `read_buffer_size` bytes and whose uploads are sent in parts of
+    /// `write_chunk_size` bytes, for calls that don't set their own
+    /// `chunk_size`. Small ranges suit sftp, large parts suit s3 or gcs;
+    /// `NULL` keeps the current setting.
+    pub fn with_buffer_sizes(
+        &self,
+        #[default = "NULL"] read_buffer_size: Option<f64>,
+        #[default = "NULL"] write_chunk_size: Option<f64>,
+    ) -> Result<Self> {
+        let mut sizes = self.sizes;
+        if let Some(n) = read_buffer_size {
+            sizes.read_buffer = Some(positive_u32(n, "read_buffer_size")? as usize);
+        }
+        if let Some(n) = write_chunk_size {
+            sizes.write_chunk = Some(positive_u32(n, "write_chunk_size")? as usize);
+        }
+
+        Ok(Self {
+            op: self.op.clone(),
+            sizes,
+        })
+    }
+
+    /// Returns an operator whose uploads are limited to
+    /// `bandwidth_bytes_per_sec`, letting up to `burst` bytes (default: one
+    /// second's worth) through at once. opendal's throttle only paces
+    /// writes; downloads are not limited.
+    pub fn with_throttle(
+        &self,
+        bandwidth_bytes_per_sec: f64,
+        #[default = "NULL"] burst: Option<f64>,
+    ) -> Result<Self> {
+        let bandwidth = positive_u32(bandwidth_bytes_per_sec, "bandwidth_bytes_per_sec")?;
+        let burst = match burst {
+            Some(b) => positive_u32(b, "burst")?,
+            None => bandwidth,
+        };
+
+        let throttled = self.layer(ThrottleLayer::new(bandwidth, burst))?;
+        throttled.layer(SplitWritesLayer::new(burst as usize))
+    }
+
+    /// Returns an operator that fills in `content_type` from the file
+    /// extension on writes without one, and on `stat()` results where the
+    /// service reports none.
+    pub fn with_mime_guess(&self) -> Result<Self> {
+        self.layer(MimeGuessLayer::default())
+    }
+
+    /// Returns an operator that lists from a fixed manifest of `paths`
+    /// (directories end in `/`) instead of asking the service, so backends
+    /// that can't list, such as plain HTTP, can still be walked.
+    pub fn with_index(&self, paths: Vec<String>) -> Result<Self> {
+        let mut layer = ImmutableIndexLayer::default();
+        layer.extend_iter(
+            paths
+                .into_iter()
+                .map(|p| p.trim_start_matches('/').to_string()),
+        );
+
+        self.layer(layer)
+    }
+
+    /// Returns an operator that keeps whole objects it reads under `dir`,
+    /// so reading the same object again costs only a `stat()` to check its
+    /// etag. Least recently used entries are evicted beyond `max_bytes`;
+    /// entries checked less than `ttl` seconds ago are reused without
+    /// asking the service at all.
+    pub fn with_disk_cache(
+        &self,
+        dir: &str,
+        #[default = "NULL"] max_bytes: Option<f64>,
+        #[default = "NULL"] ttl: Option<f64>,
+    ) -> Result<Self> {
+        let (max_bytes, ttl) = cache_limits(max_bytes, ttl)?;
+        let store = DiskStore::new(dir.into(), max_bytes)
+            .map_err(|e| anyhow::anyhow!("can't create cache directory `{dir}`: {e}"))?;
+
+        self.layer(CacheLayer::new(store, ttl))
+    }
+
+    /// Returns an operator that keeps up to `max_bytes` of objects it reads
+    /// in memory for the session, for small files read over and over. Each
+    /// read still stats the object and refetches it if its etag changed.
+    pub fn with_memory_cache(&self, max_bytes: f64) -> Result<Self> {
+        let (max_bytes, _) = cache_limits(Some(max_bytes), None)?;
+        let store = MemoryStore::new(max_bytes.unwrap_or_default());
+
+        self.layer(CacheLayer::new(store, None))
+    }
+
+    /// Returns an operator that remembers what `stat()` and `exists()` found
+    /// for each path, missing paths included, so checking the same paths
+    /// again skips the service. Changes made through the operator drop the
+    /// entries they affect; for anything else, entries are trusted for `ttl`
+    /// seconds, or until `invalidate_cache()` if there is no `ttl`.
+    pub fn with_metadata_cache(&self, #[default = "NULL"] ttl: Option<f64>) -> Result<Self> {
+        let (_, ttl) = cache_limits(None, ttl)?;
+        self.layer(StatCacheLayer::new(ttl))
+    }
+
+    /// Forgets cached metadata for every path below `prefix`, or everything
+    /// for this operator's service and root by default. Any operator on the
+    /// same service can invalidate them, cached or not.
+    pub fn invalidate_cache(&self, #[default = "\"\""] prefix: &str) {
+        stat_cache::invalidate(&self.op.info(), prefix);
+    }
+
+    /// Returns an operator that encrypts objects before they are written and
+    /// decrypts them as they are read, so the service only ever sees
+    /// ciphertext. `key` is 32 random bytes, as a raw vector or base64
+    /// string. Objects written without the layer, or with another key, fail
+    /// to read.
+    pub fn with_encryption(
+        &self,
+        key: Robj,
+        #[default = "\"aes-256-gcm\""] algorithm: &str,
+    ) -> Result<Self> {
+        let algorithm = Algorithm::from_str(algorithm)?;
+        let key = if let Some(raw) = key.as_raw() {
+            raw.as_slice().to_vec()
+        } else if let Some(s) = key.as_str() {
+            BASE64_STANDARD
+                .decode(s)
+                .map_err(|e| anyhow::anyhow!("`key` is not valid base64: {e}"))?
+        } else {
+            anyhow::bail!("`key` must be a raw vector or a base64 string");
+        };
+
+        self.layer(EncryptionLayer::new(algorithm, &key)?)
+    }
+
+    /// Returns an operator that emits an OpenTelemetry span for each
+    /// storage operation. With an `endpoint` (or `OTEL_EXPORTER_OTLP_ENDPOINT`)
+    /// spans are exported over OTLP/HTTP under `service_name`; otherwise they
+    /// go to whichever tracer provider is already installed. Needs the `otel`
+    /// feature.
+    pub fn with_tracing(
+        &self,
+        #[default = "NULL"] endpoint: Option<String>,
+        #[default = "NULL"] service_name: Option<String>,
+    ) -> Result<Self> {
+        #[cfg(feature = "otel")]
+        {
+            let endpoint = endpoint
+                .or_else(|| std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok())
+                .filter(|e| !e.is_empty());
+            if let Some(ep) = endpoint {
+                let name = service_name
+                    .or_else(|| std::env::var("OTEL_SERVICE_NAME").ok())
+                    .unwrap_or_else(|| "opendalr".to_string());
+                otel::init(&ep, &name)?;
+            }
+
+            self.layer(OtelTraceLayer)
+        }
+
+        #[cfg(not(feature = "otel"))]
+        {
+            let _ = (endpoint, service_name);
+            anyhow::bail!("opendalr was built without the `otel` feature")
+        }
+    }
+
+    /// Sends spans still buffered by the OTLP exporter, e.g. before a
+    /// short-lived script exits.
+    pub fn flush_tracing(&self) -> Result<()> {
+        #[cfg(feature = "otel")]
+        {
+            otel::flush()
+        }
+
+        #[cfg(not(feature = "otel"))]
+        {
+            anyhow::bail!("opendalr was built without the `otel` feature")
+        }
+    }
+
+    /// Per-operation counts, errors, bytes and latency percentiles (in
+    /// seconds) recorded for this operator's service during the session.
+    pub fn metrics(&self) -> Robj {
+        let stats = metrics::snapshot(&self.op.info());
+        let latency = |p: f64| -> Vec<Option<f64>> { stats.iter().map(|s| s.latency(p)).collect() };
+
+        data_frame!(
+            operation = stats.iter().map(|s| s.operation).collect::<Vec<_>>(),
+            count = stats.iter().map(|s| s.count as f64).collect::<Vec<_>>(),
+            errors = stats.iter().map(|s| s.errors as f64).collect::<Vec<_>>(),
+            bytes = stats.iter().map(|s| s.bytes as f64).collect::<Vec<_>>(),
+            latency_p50 = latency(0.5),
+            latency_p90 = latency(0.9),
+            latency_p99 = latency(0.99),
+            stringsAsFactors = false
+        )
+    }
+
+    // General Paths
+    /// Checks whether each of `paths` exists, running the checks
+    /// concurrently.
+    pub fn exists(&self, paths: Vec<String>) -> Result<Vec<bool>> {
+        let op = Operator::from(self.op.clone());
+        let checks = futures::stream::iter(&paths)
+            .map(|path| op.exists(path))
+            .buffered(CONCURRENT_REQUESTS)
+            .collect::<Vec<_>>();
+        let found = runtime().block_on(checks);
+        Ok(found.into_iter().collect::<opendal::Result<_>>()?)
+    }
+
+    /// Stats each of `paths` concurrently, returning a data.frame with one
+    /// row per path: whether it exists, then is_dir, size, last_modified,
+    /// etag and content_type (`NA` if unknown or missing), and the error
+    /// that stopped the stat, if any besides the path not existing.
+    pub fn stat_many(&self, paths: Vec<String>) -> Result<Robj> {
+        let op = Operator::from(self.op.clone());
+        let stats = futures::stream::iter(&paths)
+            .map(|path| op.stat(path))
+            .buffered(CONCURRENT_REQUESTS)
+            .collect::<Vec<_>>();
+        let stats = runtime().block_on(stats);
+
+        let meta: Vec<Option<&Metadata>> = stats.iter().map(|r| r.as_ref().ok()).collect();
+        let text = |f: fn(&Metadata) -> Option<&str>| -> Vec<Option<String>> {
+            meta.iter().map(|m| m.and_then(f).map(str::to_string)).collect()
+        };
+        let last_modified = meta
+            .iter()
+            .map(|m| m.and_then(Metadata::last_modified).map(epoch_secs))
+            .collect();
+        let last_modified = posixct(last_modified)?;
+        let error: Vec<Option<String>> = stats
+            .iter()
+            .map(|r| match r {
+                Err(e) if e.kind() != ErrorKind::NotFound => Some(e.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        Ok(data_frame!(
+            path = paths,
+            exists = meta.iter().map(|m| m.is_some()).collect::<Vec<_>>(),
+            is_dir = meta.iter().map(|m| m.map(Metadata::is_dir)).collect::<Vec<_>>(),
+            size = meta
+                .iter()
+                .map(|m| m.filter(|m| m.is_file()).map(|m| m.content_length() as f64))
+                .collect::<Vec<_>>(),
+            last_modified = last_modified,
+            etag = text(Metadata::etag),
+            content_type = text(Metadata::content_type),
+            error = error,
+            stringsAsFactors = false
+        ))
+    }
+
+    /// Retrieves metadata for a path. The conditions and `version` work as
+    /// for `read_raw()`, so an unchanged etag can be detected without
+    /// downloading anything.
+    pub fn stat(
+        &self,
+        path: &str,
+        #[default = "NULL"] if_match: Option<String>,
+        #[default = "NULL"] if_none_match: Option<String>,
+        #[default = "NULL"] if_modified_since: Option<f64>,
+        #[default = "NULL"] version: Option<String>,
+    ) -> Result<OpenDALMetadata> {
+        let if_modified_since = if_modified_since.map(timestamp).transpose()?;
+
+        let op = Operator::from(self.op.clone());
+        let mut stat = op.stat_with(path);
+        if let Some(v) = &if_match {
+            stat = stat.if_match(v);
+        }
+        if let Some(v) = &if_none_match {
+            stat = stat.if_none_match(v);
+        }
+        if let Some(v) = if_modified_since {
+            stat = stat.if_modified_since(v);
+        }
+        if let Some(v) = &version {
+            stat = stat.version(v);
+        }
+
+        let meta = runtime().block_on(async { stat.await })?;
+        Ok(OpenDALMetadata::from(meta))
+    }
+
+    // Directories
+    pub fn create_dir(&self, path: &str) -> Result<()> {
+        Ok(self.op.create_dir(path)?)
+    }
+
+    /// Lists the entries directly under `path` by name. With `recursive`,
+    /// lists everything below `path` instead, by path relative to `path`.
+    ///
+    /// `limit` caps how many entries are returned and `start_after` skips
+    /// entries whose paths sort before or equal to it, to resume a listing.
+    /// With `versions`, every version of each object is listed, on services
+    /// that keep them.
+    pub fn list(
+        &self,
+        path: &str,
+        #[default = "FALSE"] recursive: bool,
+        #[default = "NULL"] limit: Option<f64>,
+        #[default = "NULL"] start_after: Option<String>,
+        #[default = "FALSE"] versions: bool,
+    ) -> Result<Vec<String>> {
+        let entries = self
+            .open_lister(path, recursive, limit, start_after, versions)?
+            .entries()?;
+        if !recursive {
+            return Ok(entries
+                .into_iter()
+                .map(|entry| entry.name().to_string())
+                .collect());
+        }
+
+        let prefix = path.trim_start_matches('/');
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let path = entry.path();
+                path.strip_prefix(prefix).unwrap_or(path).to_string()
+            })
+            // Drop `path` itself, which some services list too.
+            .filter(|path| !path.is_empty() && path != "/")
+            .collect())
+    }
+
+    /// Lists `path` as a data.frame of each entry's name, path, is_dir,
+    /// size, last_modified, etag, content_type and version, `NA` where the
+    /// service doesn't report them. Services whose listings carry no sizes
+    /// (e.g. fs, memory) are stat'ed per file instead. The options work as
+    /// for `list()`.
+    pub fn list_detail(
+        &self,
+        path: &str,
+        #[default = "FALSE"] recursive: bool,
+        #[default = "NULL"] limit: Option<f64>,
+        #[default = "NULL"] start_after: Option<String>,
+        #[default = "FALSE"] versions: bool,
+    ) -> Result<Robj> {
+        let listed_sizes = self.op.info().full_capability().list_has_content_length;
+
+        let mut entries = Vec::new();
+        let mut lister = self.open_lister(path, recursive, limit, start_after, versions)?;
+        for entry in lister.entries()? {
+            let name = entry.name().to_string();
+            let (entry_path, meta) = entry.into_parts();
+            let meta = if meta.is_file() && !listed_sizes {
+                self.op.stat(&entry_path)?
+            } else {
+                meta
+            };
+            entries.push((name, entry_path, meta));
+        }
+
+        entry_frame(&entries)
+    }
+
+    /// Opens a paginated listing of `path`, with the options of `list()`.
+    pub fn lister(
+        &self,
+        path: &str,
+        #[default = "FALSE"] recursive: bool,
+        #[default = "NULL"] limit: Option<f64>,
+        #[default = "NULL"] start_after: Option<String>,
+        #[default = "FALSE"] versions: bool,
+    ) -> Result<OpenDALLister> {
+        self.open_lister(path, recursive, limit, start_after, versions)
+    }
+
+    /// Lists every version of the object at `path`, or of each object
+    /// directly under it when `path` ends in "/", as a data.frame of path,
+    /// version, size, last_modified and is_latest. Needs a service that
+    /// keeps versions, such as s3 or gcs with versioning enabled.
+    pub fn list_versions(&self, path: &str) -> Result<Robj> {
+        let entries = self.open_lister(path, false, None, None, true)?.entries()?;
+        let versions: Vec<Entry> = entries
+            .into_iter()
+            .filter(|e| e.metadata().is_file() && (path.ends_with('/') || e.path() == path))
+            .collect();
+        let last_modified = versions
+            .iter()
+            .map(|e| e.metadata().last_modified().map(epoch_secs))
+            .collect();
+        let last_modified = posixct(last_modified)?;
+
+        Ok(data_frame!(
+            path = versions.iter().map(|e| e.path()).collect::<Vec<_>>(),
+            version = versions
+                .iter()
+                .map(|e| e.metadata().version().map(str::to_string))
+                .collect::<Vec<_>>(),
+            size = versions
+                .iter()
+                .map(|e| e.metadata().content_length() as f64)
+                .collect::<Vec<_>>(),
+            last_modified = last_modified,
+            is_latest = versions
+                .iter()
+                .map(|e| e.metadata().is_current())
+                .collect::<Vec<_>>(),
+            stringsAsFactors = false
+        ))
+    }
+
+    /// Lists the files whose paths match `pattern`, e.g.
+    /// `"data/2024-*/**/*.parquet"`: `*` and `?` stay within a directory,
+    /// `**` crosses them and `[...]` matches one of a set. Only the tree
+    /// below the pattern's leading literal directories is listed, and only
+    /// recursively when the pattern reaches further down.
+    pub fn glob(&self, pattern: &str) -> Result<Vec<String>> {
+        let pattern = pattern.trim_start_matches('/');
+        let matcher = glob::Pattern::new(pattern)?;
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+
+        let wildcard = pattern.find(['*', '?', '[']).unwrap_or(pattern.len());
+        let base = &pattern[..pattern[..wildcard].rfind('/').map_or(0, |i| i + 1)];
+        let rest = &pattern[base.len()..];
+        let recursive = rest.contains('/') || rest.contains("**");
+
+        let mut paths = Vec::new();
+        for entry in self.op.lister_with(base).recursive(recursive).call()? {
+            let entry = entry?;
+            if entry.metadata().is_file() && matcher.matches_with(entry.path(), options) {
+                paths.push(entry.path().to_string());
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Lists the files below `prefix` whose paths, relative to `prefix`,
+    /// match the regular expression `regex`. When `regex` is anchored with
+    /// `^`, its leading literal directories narrow the listing sent to the
+    /// service; entries are filtered as they stream in, so only matches are
+    /// held in memory.
+    pub fn list_matching(&self, prefix: &str, regex: &str) -> Result<Vec<String>> {
+        let matcher = regex::Regex::new(regex)?;
+        let base = transfer::dir_prefix(prefix);
+        let literal = regex_prefix(regex);
+        let narrowed = &literal[..literal.rfind('/').map_or(0, |i| i + 1)];
+
+        let mut paths = Vec::new();
+        let listing = format!("{base}{narrowed}");
+        for entry in self.op.lister_with(&listing).recursive(true).call()? {
+            let entry = entry?;
+            let relative = entry.path().strip_prefix(&base).unwrap_or(entry.path());
+            if entry.metadata().is_file() && matcher.is_match(relative) {
+                paths.push(entry.path().to_string());
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Totals the objects below `prefix` and their bytes, walking the
+    /// listing as it streams in. With `by = "per_dir"`, totals are grouped
+    /// by first-level directory instead, `"."` holding the objects directly
+    /// under `prefix`. Returns a data.frame of dir, objects and bytes.
+    pub fn du(&self, prefix: &str, #[default = "\"total\""] by: &str) -> Result<Robj> {
+        let per_dir = match by {
+            "total" => false,
+            "per_dir" => true,
+            _ => anyhow::bail!("`by` must be \"total\" or \"per_dir\", not \"{by}\""),
+        };
+        let base = transfer::dir_prefix(prefix);
+
+        let mut totals: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+        if !per_dir {
+            totals.insert(prefix.to_string(), (0, 0));
+        }
+        self.walk_files(&base, |path, size| {
+            let group = if per_dir {
+                let relative = path.strip_prefix(&base).unwrap_or(&path);
+                match relative.split_once('/') {
+                    Some((dir, _)) => format!("{dir}/"),
+                    None => ".".to_string(),
+                }
+            } else {
+                prefix.to_string()
+            };
+            let total = totals.entry(group).or_default();
+            total.0 += 1;
+            total.1 += size;
+        })?;
+
+        Ok(data_frame!(
+            dir = totals.keys().cloned().collect::<Vec<_>>(),
+            objects = totals.values().map(|t| t.0 as f64).collect::<Vec<_>>(),
+            bytes = totals.values().map(|t| t.1 as f64).collect::<Vec<_>>(),
+            stringsAsFactors = false
+        ))
+    }
+
+    /// Draws the tree below `prefix` as an indented character vector, one
+    /// line per directory or file with its size, for `writeLines()`. The
+    /// first line is `prefix` itself; `max_depth` limits how many levels
+    /// below it are shown.
+    pub fn tree(&self, prefix: &str, #[default = "Inf"] max_depth: f64) -> Result<Vec<String>> {
+        let max_depth = if max_depth.is_infinite() {
+            usize::MAX
+        } else {
+            positive_u32(max_depth, "max_depth")? as usize
+        };
+        let base = transfer::dir_prefix(prefix);
+
+        let mut root = TreeDir::default();
+        self.walk_files(&base, |path, size| {
+            root.insert(path.strip_prefix(&base).unwrap_or(&path), size);
+        })?;
+
+        let name = if base.is_empty() { "/" } else { &base };
+        let mut lines = vec![format!("{name} ({})", format_bytes(root.bytes))];
+        root.render(1, max_depth.saturating_add(1), &mut lines);
+        Ok(lines)
+    }
+
+    // Files
+    /// Reads the whole object. With `if_match`, `if_none_match` (etags) or
+    /// `if_modified_since` (a POSIXct), the read fails with a
+    /// `ConditionNotMatch` error when the condition doesn't hold, e.g. when
+    /// a cached copy is still current.
+    ///
+    /// `version` reads a historical version by the id `list_versions()`
+    /// reports, on services that keep versions.
+    ///
+    /// `chunk_size` fetches the object in ranges of that many bytes (by
+    /// default, as set by `with_buffer_sizes()`). With a `concurrency` above
+    /// one, that many ranges are fetched at a time, which is much faster
+    /// for large objects on high-latency links such as s3 or gcs.
+    #[allow(clippy::too_many_arguments)]
+    pub fn read_raw(
+        &self,
+        path: &str,
+        #[default = "NULL"] if_match: Option<String>,
+        #[default = "NULL"] if_none_match: Option<String>,
+        #[default = "NULL"] if_modified_since: Option<f64>,
+        #[default = "NULL"] version: Option<String>,
+        #[default = "NULL"] chunk_size: Option<f64>,
+        #[default = "1"] concurrency: f64,
+    ) -> Result<Robj> {
+        let if_modified_since = if_modified_since.map(timestamp).transpose()?;
+        let concurrency = positive_u32(concurrency, "concurrency")? as usize;
+        let chunk_size = self.read_chunk(chunk_size, concurrency)?;
+
+        // The blocking API has no `if_modified_since`, so use the async one.
+        let op = Operator::from(self.op.clone());
+        let mut read = op.read_with(path);
+        if let Some(v) = &if_match {
+            read = read.if_match(v);
+        }
+        if let Some(v) = &if_none_match {
+            read = read.if_none_match(v);
+        }
+        if let Some(v) = if_modified_since {
+            read = read.if_modified_since(v);
+        }
+        if let Some(v) = &version {
+            read = read.version(v);
+        }
+        if let Some(n) = chunk_size {
+            read = read.chunk(n).concurrent(concurrency);
+        }
+
+        let content = runtime().block_on(async { read.await })?;
+        Ok(raw_vector(content))
+    }
+
+    /// Reads `length` bytes starting at byte `offset`, fewer if the object
+    /// ends first. Only that range is downloaded.
+    pub fn read_range(&self, path: &str, offset: f64, length: f64) -> Result<Robj> {
+        let offset = whole_u64(offset, "offset")?;
+        let length = whole_u64(length, "length")?;
+
+        // Key-value backends panic on ranges past the end, so clamp first.
+        let size = self.op.stat(path)?.content_length();
+        let end = offset.saturating_add(length).min(size);
+        if offset >= end {
+            return Ok(Raw::new(0).into());
+        }
+
+        let content = self.op.read_with(path).range(offset..end).call()?;
+        Ok(raw_vector(content))
+    }
+
+    /// Reads each of `paths` in full, at most `max_concurrency` at a time,
+    /// returning a list of raw vectors named by path.
+    pub fn read_many(
+        &self,
+        paths: Vec<String>,
+        #[default = "16"] max_concurrency: f64,
+    ) -> Result<List> {
+        let limit = positive_u32(max_concurrency, "max_concurrency")? as usize;
+
+        let op = Operator::from(self.op.clone());
+        let reads = futures::stream::iter(&paths)
+            .map(|path| op.read(path))
+            .buffered(limit)
+            .collect::<Vec<_>>();
+        let contents = runtime()
+            .block_on(reads)
+            .into_iter()
+            .map(|content| content.map(raw_vector))
+            .collect::<opendal::Result<Vec<_>>>()?;
+
+        List::from_names_and_values(&paths, contents).map_err(|e| anyhow::anyhow!("{e}"))
+    }
+
+    /// Reads up to `n` lines of text from `path` (all of them when `n` is
+    /// `Inf` or negative), streaming so only the lines returned are held in
+    /// memory. Line endings may be "\n" or "\r\n".
+    pub fn read_lines(&self, path: &str, #[default = "Inf"] n: f64) -> Result<Vec<String>> {
+        let n = if n < 0.0 || n.is_infinite() {
+            usize::MAX
+        } else {
+            whole_u64(n, "n")? as usize
+        };
+
+        let reader = BufReader::new(self.op.reader(path)?.into_std_read(..)?);
+        let mut lines = Vec::new();
+        for line in reader.split(b'\n').take(n) {
+            let mut line = line?;
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            lines.push(String::from_utf8_lossy(&line).into_owned());
+        }
+        Ok(lines)
+    }
+
+    /// Writes `lines` to `path`, each followed by `sep`, uploading as it
+    /// goes rather than building the whole file first.
+    pub fn write_lines(
+        &self,
+        path: &str,
+        lines: Vec<String>,
+        #[default = "\"\\n\""] sep: &str,
+    ) -> Result<()> {
+        const BATCH: usize = 1 << 20;
+
+        let mut writer = self.op.writer(path)?;
+        let mut batch = Vec::with_capacity(BATCH);
+        for line in &lines {
+            batch.extend_from_slice(line.as_bytes());
+            batch.extend_from_slice(sep.as_bytes());
+            if batch.len() >= BATCH {
+                writer.write(std::mem::replace(&mut batch, Vec::with_capacity(BATCH)))?;
+            }
+        }
+        if !batch.is_empty() {
+            writer.write(batch)?;
+        }
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Opens `path` for incremental reading with `read(n)`, `seek(pos)`,
+    /// `tell()` and `close()`.
+    pub fn reader(&self, path: &str) -> Result<OpenDALReader> {
+        let reader = self.op.reader(path)?.into_std_read(..)?;
+        Ok(OpenDALReader {
+            path: path.to_string(),
+            reader: Some(reader),
+        })
+    }
+
+    /// Opens `path` as an R connection, so functions that take one, such as
+    /// `readRDS()`, `read.csv()` or `saveRDS()`, can read or write the
+    /// object directly. `mode` is `"rb"` or `"wb"` for binary, `"r"` or
+    /// `"w"` for text; `close()` the connection when done, which commits a
+    /// written object.
+    pub fn connection(&self, path: &str, #[default = "\"rb\""] mode: &str) -> Result<Robj> {
+        connection::open(self.op.clone(), path, mode)
+    }
+
+    /// Opens `path` for streaming writes with `write(data)`, then `close()`
+    /// to commit the object or `abort()` to discard it. With `append`, data
+    /// is added to the end of an existing object instead. `chunk_size` (by
+    /// default, as set by `with_buffer_sizes()`) buffers writes into parts
+    /// of that size on services with multipart uploads.
+    pub fn writer(
+        &self,
+        path: &str,
+        #[default = "FALSE"] append: bool,
+        #[default = "NULL"] chunk_size: Option<f64>,
+    ) -> Result<OpenDALWriter> {
+        if append {
+            self.check_append()?;
+        }
+        let chunk_size = self.write_chunk(chunk_size)?;
+
+        // Blocking writers can't be aborted, so drive the async one.
+        let op = Operator::from(self.op.clone());
+        let mut write = op.writer_with(path).append(append);
+        if let (Some(n), true) = (chunk_size, op.info().full_capability().write_can_multi) {
+            write = write.chunk(n);
+        }
+        let writer = runtime().block_on(async { write.await })?;
+        Ok(OpenDALWriter {
+            path: path.to_string(),
+            writer: Some(writer),
+        })
+    }
+
+    /// Writes `data` to `path`, optionally setting the headers the object
+    /// is later served with. Services without a place for a header ignore
+    /// it.
+    ///
+    /// With `if_not_exists`, or `if_none_match` set to an etag (or "*"),
+    /// the write fails with a `ConditionNotMatch` error instead of
+    /// replacing an existing object, so only one of several racing writers
+    /// wins. Services that can't check the condition reject the write.
+    ///
+    /// `metadata`, a named character vector, is stored with the object on
+    /// services that keep user metadata (e.g. s3, gcs, azblob) and read back
+    /// with `stat()$user_metadata()`. Other services reject it.
+    ///
+    /// On services with multipart uploads, such as s3 or gcs, `data` larger
+    /// than `chunk_size` bytes (by default, as set by `with_buffer_sizes()`,
+    /// else 8 MiB) is sent as parts, up to `concurrency` at once.
+    #[allow(clippy::too_many_arguments)]
+    pub fn write(
+        &self,
+        path: &str,
+        data: Vec<u8>,
+        #[default = "NULL"] content_type: Option<String>,
+        #[default = "NULL"] cache_control: Option<String>,
+        #[default = "NULL"] content_disposition: Option<String>,
+        #[default = "NULL"] content_encoding: Option<String>,
+        #[default = "FALSE"] if_not_exists: bool,
+        #[default = "NULL"] if_none_match: Option<String>,
+        #[default = "NULL"] metadata: Robj,
+        #[default = "NULL"] chunk_size: Option<f64>,
+        #[default = "4"] concurrency: f64,
+    ) -> Result<()> {
+        let metadata = user_metadata(&metadata)?;
+        let chunk_size = self.write_chunk(chunk_size)?.unwrap_or(transfer::CHUNK_SIZE);
+        let concurrency = positive_u32(concurrency, "concurrency")? as usize;
+        let info = self.op.info();
+        if !metadata.is_empty() && !info.full_capability().write_with_user_metadata {
+            anyhow::bail!("the `{}` service doesn't store user metadata", info.scheme());
+        }
+
+        // The blocking API has no content encoding, so go through the async one.
+        let op = Operator::from(self.op.clone());
+        let mut write = op.write_with(path, data);
+        if let Some(v) = &content_type {
+            write = write.content_type(v);
+        }
+        if let Some(v) = &cache_control {
+            write = write.cache_control(v);
+        }
+        if let Some(v) = &content_disposition {
+            write = write.content_disposition(v);
+        }
+        if let Some(v) = &content_encoding {
+            write = write.content_encoding(v);
+        }
+        if if_not_exists {
+            write = write.if_not_exists(true);
+        }
+        if let Some(v) = &if_none_match {
+            write = write.if_none_match(v);
+        }
+        if !metadata.is_empty() {
+            write = write.user_metadata(metadata);
+        }
+        if info.full_capability().write_can_multi {
+            write = write.chunk(chunk_size).concurrent(concurrency);
+        }
+
+        let _ = runtime().block_on(async { write.await })?;
+        Ok(())
+    }
+
+    /// Writes a string to `path`, encoded as `encoding`, and served with
+    /// `content_type`.
+    pub fn write_text(
+        &self,
+        path: &str,
+        text: &str,
+        #[default = "\"UTF-8\""] encoding: &str,
+        #[default = "\"text/plain\""] content_type: &str,
+    ) -> Result<()> {
+        let data = encode_text(text, encoding)?;
+        let _ = self.op.write_with(path, data).content_type(content_type).call()?;
+        Ok(())
+    }
+
+    /// Uploads a named list of raw vectors, one object per name, at most
+    /// `max_concurrency` at a time. Every upload is attempted; the result
+    /// is a data.frame with one row per path, whether it was written, and
+    /// the error if it wasn't.
+    pub fn write_many(&self, data: List, #[default = "16"] max_concurrency: f64) -> Result<Robj> {
+        let limit = positive_u32(max_concurrency, "max_concurrency")? as usize;
+
+        let mut objects = Vec::with_capacity(data.len());
+        for (name, value) in data.iter() {
+            if name.is_empty() || name == "NA" {
+                anyhow::bail!("every element of `data` must be named by its path");
+            }
+            let Some(bytes) = value.as_raw_slice() else {
+                anyhow::bail!("`data${name}` must be a raw vector");
+            };
+            objects.push((name.to_string(), bytes.to_vec()));
+        }
+
+        let op = Operator::from(self.op.clone());
+        let writes = futures::stream::iter(objects)
+            .map(|(path, bytes)| {
+                let op = op.clone();
+                async move { (path.clone(), op.write(&path, bytes).await) }
+            })
+            .buffered(limit)
+            .collect::<Vec<_>>();
+        let (paths, error): (Vec<String>, Vec<Option<String>>) = runtime()
+            .block_on(writes)
+            .into_iter()
+            .map(|(path, r)| (path, r.err().map(|e| e.to_string())))
+            .unzip();
+        Ok(data_frame!(
+            path = paths,
+            written = error.iter().map(Option::is_none).collect::<Vec<_>>(),
+            error = error,
+            stringsAsFactors = false
+        ))
+    }
+
+    /// Streams the local file at `local_path` to `remote_path`, reading
+    /// `chunk_size` bytes at a time (by default, as set by
+    /// `with_buffer_sizes()`, else 8 MiB), and returns the number of bytes
+    /// sent. On services with multipart uploads, such as s3 or gcs, each chunk is
+    /// a part and up to `concurrency` parts are sent at once. A failed
+    /// upload is aborted rather than left half written.
+    pub fn upload_file(
+        &self,
+        local_path: &str,
+        remote_path: &str,
+        #[default = "NULL"] chunk_size: Option<f64>,
+        #[default = "4"] concurrency: f64,
+    ) -> Result<f64> {
+        let chunk_size = self.write_chunk(chunk_size)?.unwrap_or(transfer::CHUNK_SIZE);
+        let concurrency = positive_u32(concurrency, "concurrency")? as usize;
+        let op = Operator::from(self.op.clone());
+        let local = Path::new(local_path);
+        let upload = transfer::upload(&op, local, remote_path, chunk_size, concurrency);
+        let sent = runtime().block_on(upload)?;
+        Ok(sent as f64)
+    }
+
+    /// Streams `remote_path` to the local file at `local_path`, so objects
+    /// too large for an R raw vector can still be fetched, and returns the
+    /// number of bytes received. `version` works as for `read_raw()`. The
+    /// download goes to `<local_path>.part` first, so a failure never leaves
+    /// a truncated file behind. `chunk_size` and `concurrency` split the
+    /// download into ranges as for `read_raw()`.
+    pub fn download_file(
+        &self,
+        remote_path: &str,
+        local_path: &str,
+        #[default = "NULL"] version: Option<String>,
+        #[default = "NULL"] chunk_size: Option<f64>,
+        #[default = "1"] concurrency: f64,
+    ) -> Result<f64> {
+        let concurrency = positive_u32(concurrency, "concurrency")? as usize;
+        let chunk_size = self.read_chunk(chunk_size, concurrency)?;
+        let op = Operator::from(self.op.clone());
+        let local = Path::new(local_path);
+        let version = version.as_deref();
+        let download =
+            transfer::download(&op, remote_path, local, version, chunk_size, concurrency);
+        Ok(runtime().block_on(download)? as f64)
+    }
+
+    /// Uploads every file below `local_dir` to the same relative path under
+    /// `remote_prefix`, at most `max_concurrency` at a time. `include` and
+    /// `exclude` are globs matched against paths relative to `local_dir`
+    /// (`*` stays within a directory, `**` crosses them). Every upload is
+    /// attempted; the result is a data.frame with one row per file: its
+    /// remote and local path, size, whether it was uploaded, and the error
+    /// if it wasn't.
+    pub fn upload_dir(
+        &self,
+        local_dir: &str,
+        remote_prefix: &str,
+        #[default = "NULL"] include: Option<Vec<String>>,
+        #[default = "NULL"] exclude: Option<Vec<String>>,
+        #[default = "16"] max_concurrency: f64,
+    ) -> Result<Robj> {
+        let limit = positive_u32(max_concurrency, "max_concurrency")? as usize;
+        let chunk_size = self.write_chunk(None)?.unwrap_or(transfer::CHUNK_SIZE);
+        let filter = PathFilter::new(include, exclude)?;
+        let mut files = transfer::local_files(Path::new(local_dir))?;
+        files.retain(|(relative, _, _)| filter.matches(relative));
+
+        let op = Operator::from(self.op.clone());
+        let uploads = futures::stream::iter(&files)
+            .map(|(relative, local, _)| {
+                let remote = transfer::remote_path(remote_prefix, relative);
+                let op = &op;
+                async move { transfer::upload(op, local, &remote, chunk_size, 1).await }
+            })
+            .buffered(limit)
+            .collect::<Vec<_>>();
+        let error: Vec<Option<String>> = runtime()
+            .block_on(uploads)
+            .into_iter()
+            .map(|r| r.err().map(|e| e.to_string()))
+            .collect();
+
+        Ok(data_frame!(
+            path = files
+                .iter()
+                .map(|(relative, _, _)| transfer::remote_path(remote_prefix, relative))
+                .collect::<Vec<_>>(),
+            local_path = files
+                .iter()
+                .map(|(_, local, _)| local.to_string_lossy().into_owned())
+                .collect::<Vec<_>>(),
+            size = files.iter().map(|(_, _, size)| *size as f64).collect::<Vec<_>>(),
+            uploaded = error.iter().map(Option::is_none).collect::<Vec<_>>(),
+            error = error,
+            stringsAsFactors = false
+        ))
+    }
+
+    /// Mirrors every object below `remote_prefix` into `local_dir`, creating
+    /// directories as needed, at most `max_concurrency` at a time. Files
+    /// that already match are skipped: same size and, where the etag is an
+    /// MD5 digest, same content. `include` and `exclude` work as for
+    /// `upload_dir()`, against paths relative to `remote_prefix`. The result
+    /// is a data.frame with one row per object: its remote and local path,
+    /// size, whether it was downloaded or skipped, and the error if any.
+    pub fn download_dir(
+        &self,
+        remote_prefix: &str,
+        local_dir: &str,
+        #[default = "NULL"] include: Option<Vec<String>>,
+        #[default = "NULL"] exclude: Option<Vec<String>>,
+        #[default = "16"] max_concurrency: f64,
+    ) -> Result<Robj> {
+        let limit = positive_u32(max_concurrency, "max_concurrency")? as usize;
+        let chunk_size = self.read_chunk(None, 1)?;
+        let filter = PathFilter::new(include, exclude)?;
+        let listed_sizes = self.op.info().full_capability().list_has_content_length;
+
+        let base = transfer::dir_prefix(remote_prefix);
+        let op = Operator::from(self.op.clone());
+        let listing = runtime().block_on(async { op.list_with(&base).recursive(true).await })?;
+        let objects: Vec<(String, Metadata)> = listing
+            .into_iter()
+            .map(Entry::into_parts)
+            .filter(|(path, meta)| {
+                meta.is_file() && filter.matches(path.strip_prefix(&base).unwrap_or(path))
+            })
+            .collect();
+
+        let downloads = futures::stream::iter(&objects)
+            .map(|(path, meta)| {
+                let op = &op;
+                let base = &base;
+                async move {
+                    let meta = if listed_sizes {
+                        meta.clone()
+                    } else {
+                        op.stat(path).await?
+                    };
+                    let relative = path.strip_prefix(base.as_str()).unwrap_or(path);
+                    let local = transfer::local_path(Path::new(local_dir), relative)?;
+                    if transfer::up_to_date(&local, &meta)? {
+                        return Ok((meta.content_length(), false));
+                    }
+                    if let Some(parent) = local.parent() {
+                        std::fs::create_dir_all(parent).map_err(|e| {
+                            anyhow::anyhow!("can't create `{}`: {e}", parent.display())
+                        })?;
+                    }
+                    let received = transfer::download(op, path, &local, None, chunk_size, 1).await?;
+                    Ok::<_, anyhow::Error>((received, true))
+                }
+            })
+            .buffered(limit)
+            .collect::<Vec<_>>();
+        let results = runtime().block_on(downloads);
+
+        let local_paths: Vec<String> = objects
+            .iter()
+            .map(|(path, _)| {
+                let relative = path.strip_prefix(&base).unwrap_or(path);
+                Path::new(local_dir).join(relative).to_string_lossy().into_owned()
+            })
+            .collect();
+        let outcome = |f: fn(&(u64, bool)) -> bool| -> Vec<bool> {
+            results.iter().map(|r| r.as_ref().is_ok_and(f)).collect()
+        };
+        Ok(data_frame!(
+            path = objects.iter().map(|(path, _)| path.clone()).collect::<Vec<_>>(),
+            local_path = local_paths,
+            size = results
+                .iter()
+                .map(|r| r.as_ref().ok().map(|(size, _)| *size as f64))
+                .collect::<Vec<_>>(),
+            downloaded = outcome(|(_, downloaded)| *downloaded),
+            skipped = outcome(|(_, downloaded)| !*downloaded),
+            error = results
+                .iter()
+                .map(|r| r.as_ref().err().map(|e| e.to_string()))
+                .collect::<Vec<_>>(),
+            stringsAsFactors = false
+        ))
+    }
+
+    /// Adds `data` to the end of `path`, creating it if needed, on services
+    /// that support appends: fs, ftp, azdls, cos, obs and webhdfs.
+    pub fn append(&self, path: &str, data: Vec<u8>) -> Result<()> {
+        self.check_append()?;
+        let _ = self.op.write_with(path, data).append(true).call()?;
+        Ok(())
+    }
+
+    /// Creates an empty object at `path`, such as a `_SUCCESS` marker, and
+    /// returns whether it was created. An existing object keeps its
+    /// content; on fs its modification time is bumped as `touch` would,
+    /// while object stores have no way to change it in place.
+    pub fn touch(&self, path: &str) -> Result<bool> {
+        let info = self.op.info();
+        let created = if info.full_capability().write_with_if_not_exists {
+            // The blocking API has no `if_not_exists`, so use the async one.
+            let op = Operator::from(self.op.clone());
+            let write = op.write_with(path, Vec::<u8>::new()).if_not_exists(true);
+            match runtime().block_on(async { write.await }) {
+                Ok(_) => true,
+                Err(e) if e.kind() == ErrorKind::ConditionNotMatch => false,
+                Err(e) => return Err(e.into()),
+            }
+        } else if !self.op.exists(path)? {
+            let _ = self.op.write(path, Vec::<u8>::new())?;
+            true
+        } else {
+            false
+        };
+
+        if !created && info.scheme() == Scheme::Fs {
+            let local = Path::new(&info.root()).join(path.trim_start_matches('/'));
+            std::fs::File::options()
+                .write(true)
+                .open(&local)
+                .and_then(|f| f.set_modified(SystemTime::now()))
+                .map_err(|e| anyhow::anyhow!("can't touch `{path}`: {e}"))?;
+        }
+        Ok(created)
+    }
+
+    pub fn delete(&self, path: &str) -> Result<()> {
+        Ok(self.op.delete(path)?)
+    }
+
+    /// Permanently deletes one version of `path`, by the id
+    /// `list_versions()` reports.
+    pub fn delete_version(&self, path: &str, version: &str) -> Result<()> {
+        Ok(self.op.delete_with(path).version(version).call()?)
+    }
+
+    /// Makes a prior version of `path` the latest again by copying it over
+    /// the current one, streamed so large objects needn't fit in memory.
+    /// Earlier versions, including the one replaced, are kept.
+    pub fn restore_version(&self, path: &str, version: &str) -> Result<()> {
+        let op = Operator::from(self.op.clone());
+        runtime().block_on(async {
+            let reader = op.reader_with(path).version(version).await?;
+            let mut chunks = reader.into_bytes_stream(..).await?;
+            let mut writer = op.writer(path).await?;
+
+            let copied: Result<()> = async {
+                while let Some(chunk) = chunks.next().await {
+                    writer.write(chunk?).await?;
+                }
+                writer.close().await?;
+                Ok(())
+            }
+            .await;
+            if copied.is_err() {
+                let _ = writer.abort().await;
+            }
+            copied
+        })
+    }
+
+    /// Deletes all of `paths`, in batches on services with a bulk delete
+    /// API (e.g. s3). Missing paths are ignored, as for `delete()`.
+    pub fn delete_many(&self, paths: Vec<String>) -> Result<()> {
+        Ok(self.op.delete_iter(paths)?)
+    }
+
+    /// Deletes the objects below the directory `prefix`, with or without a
+    /// trailing slash, whose paths relative to it match `glob` (`*` stays
+    /// within a directory, `**` crosses them) and for which `predicate`
+    /// returns `TRUE`. `predicate` is called with
+    /// pages of up to 1000 paths and must return a logical vector of the
+    /// same length. The listing is streamed, so huge prefixes are fine.
+    /// Returns the number of objects deleted.
+    pub fn delete_where(
+        &self,
+        prefix: &str,
+        #[default = "NULL"] glob: Option<String>,
+        #[default = "NULL"] predicate: Nullable<Function>,
+    ) -> Result<f64> {
+        let predicate = predicate.into_option();
+        if glob.is_none() && predicate.is_none() {
+            anyhow::bail!("supply `glob`, `predicate` or both");
+        }
+        let pattern = glob.as_deref().map(glob::Pattern::new).transpose()?;
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+
+        let base = transfer::dir_prefix(prefix);
+        let matches = |path: &str| {
+            let relative = path.strip_prefix(&base).unwrap_or(path);
+            pattern.as_ref().map_or(true, |p| p.matches_with(relative, options))
+        };
+
+        let mut deleter = self.op.deleter()?;
+        let mut lister = self.op.lister_with(&base).recursive(true).call()?.peekable();
+        let mut deleted = 0;
+        while lister.peek().is_some() {
+            let mut page = Vec::new();
+            for entry in lister.by_ref().take(1000) {
+                let entry = entry?;
+                if entry.metadata().is_file() && matches(entry.path()) {
+                    page.push(entry.path().to_string());
+                }
+            }
+
+            if let (Some(f), false) = (&predicate, page.is_empty()) {
+                let keep = f
+                    .call(pairlist!(page.clone()))
+                    .map_err(|e| anyhow::anyhow!("`predicate` failed: {e}"))?;
+                let keep = keep
+                    .as_logical_slice()
+                    .filter(|keep| keep.len() == page.len())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("`predicate` must return one TRUE or FALSE per path")
+                    })?;
+                page = page
+                    .into_iter()
+                    .zip(keep)
+                    .filter(|(_, keep)| keep.is_true())
+                    .map(|(path, _)| path)
+                    .collect();
+            }
+
+            deleted += page.len();
+            deleter.delete_iter(page)?;
+        }
+        deleter.close()?;
+
+        Ok(deleted as f64)
+    }
+
+    /// Copies an object. With `overwrite = FALSE`, fails instead if
+    /// `destination_path` exists. That check is made just before copying,
+    /// so it can't stop a concurrent writer.
+    pub fn copy(
+        &self,
+        source_path: &str,
+        destination_path: &str,
+        #[default = "TRUE"] overwrite: bool,
+    ) -> Result<()> {
+        self.check_overwrite(destination_path, overwrite)?;
+        Ok(self.op.copy(source_path, destination_path)?)
+    }
+
+    /// Moves an object; `overwrite` works as for `copy()`.
+    pub fn rename(
+        &self,
+        old_path: &str,
+        new_path: &str,
+        #[default = "TRUE"] overwrite: bool,
+    ) -> Result<()> {
+        self.check_overwrite(new_path, overwrite)?;
+        Ok(self.op.rename(old_path, new_path)?)
+    }
+
+    pub fn remove_all(&self, path: &str) -> Result<()> {
         Ok(self.op.remove_all(path)?)
     }
+
+    /// Makes `dst_prefix` on `dst` match `src_prefix` on this operator, which
+    /// may be a different service, copying only objects that are missing or
+    /// changed: a different size, a different etag when both sides are the
+    /// same kind of service, or else a source modified after the copy. With
+    /// `delete`, objects only at the destination are removed; with
+    /// `dry_run`, nothing is touched. The result is a data.frame of the
+    /// planned actions: each path relative to the prefixes, the action
+    /// (`"copy"`, `"update"` or `"delete"`), the source size, whether it
+    /// was done, and the error if it failed.
+    pub fn sync_to(
+        &self,
+        src_prefix: &str,
+        dst: &OpenDALOperator,
+        dst_prefix: &str,
+        #[default = "FALSE"] delete: bool,
+        #[default = "FALSE"] dry_run: bool,
+    ) -> Result<Robj> {
+        let src_op = Operator::from(self.op.clone());
+        let dst_op = Operator::from(dst.op.clone());
+        let src_base = transfer::dir_prefix(src_prefix);
+        let dst_base = transfer::dir_prefix(dst_prefix);
+        let (src_files, dst_files) = runtime().block_on(async {
+            futures::try_join!(
+                transfer::remote_files(&src_op, &src_base, CONCURRENT_REQUESTS),
+                transfer::remote_files(&dst_op, &dst_base, CONCURRENT_REQUESTS),
+            )
+        })?;
+
+        let comparable_etags = src_op.info().scheme() == dst_op.info().scheme();
+        let mut actions: Vec<(&str, &'static str, Option<f64>)> = Vec::new();
+        for (path, meta) in &src_files {
+            let action = match dst_files.get(path) {
+                None => "copy",
+                Some(dst_meta) if transfer::changed(meta, dst_meta, comparable_etags) => "update",
+                Some(_) => continue,
+            };
+            actions.push((path, action, Some(meta.content_length() as f64)));
+        }
+        if delete {
+            for path in dst_files.keys().filter(|p| !src_files.contains_key(*p)) {
+                actions.push((path, "delete", None));
+            }
+        }
+
+        let (done, error): (Vec<bool>, Vec<Option<String>>) = if dry_run {
+            (vec![false; actions.len()], vec![None; actions.len()])
+        } else {
+            let (src_op, dst_op) = (&src_op, &dst_op);
+            let (src_base, dst_base) = (&src_base, &dst_base);
+            let run = futures::stream::iter(&actions)
+                .map(|(path, action, _)| async move {
+                    let dst_path = format!("{dst_base}{path}");
+                    if *action == "delete" {
+                        dst_op.delete(&dst_path).await?;
+                    } else {
+                        let src_path = format!("{src_base}{path}");
+                        transfer::copy(src_op, &src_path, dst_op, &dst_path).await?;
+                    }
+                    Ok::<_, anyhow::Error>(())
+                })
+                .buffered(CONCURRENT_REQUESTS)
+                .collect::<Vec<_>>();
+            runtime()
+                .block_on(run)
+                .into_iter()
+                .map(|r| (r.is_ok(), r.err().map(|e| e.to_string())))
+                .unzip()
+        };
+
+        Ok(data_frame!(
+            path = actions.iter().map(|(path, _, _)| path.to_string()).collect::<Vec<_>>(),
+            action = actions.iter().map(|(_, action, _)| *action).collect::<Vec<_>>(),
+            size = actions.iter().map(|(_, _, size)| *size).collect::<Vec<_>>(),
+            done = done,
+            error = error,
+            stringsAsFactors = false
+        ))
+    }
+
+    // Presigning
+    /// Signs a request that downloads `path` without credentials until it
+    /// expires, for services that support it (e.g. s3, gcs, azblob).
+    pub fn presign_read(&self, path: &str, expires_secs: f64) -> Result<Robj> {
+        let expire = presign_expiry(expires_secs)?;
+        let op = Operator::from(self.op.clone());
+        let req = runtime().block_on(op.presign_read(path, expire))?;
+        presigned(req, expire, &[])
+    }
+
+    /// Signs a request that uploads `path` with a `PUT` until it expires.
+    /// With `content_type`, the upload must send that `Content-Type`, which
+    /// is then stored with the object.
+    pub fn presign_write(
+        &self,
+        path: &str,
+        expires_secs: f64,
+        #[default = "NULL"] content_type: Option<String>,
+    ) -> Result<Robj> {
+        let expire = presign_expiry(expires_secs)?;
+        let op = Operator::from(self.op.clone());
+        let mut presign = op.presign_write_with(path, expire);
+        if let Some(v) = &content_type {
+            presign = presign.content_type(v);
+        }
+        let req = runtime().block_on(async { presign.await })?;
+
+        let required: Vec<_> = content_type.iter().map(|v| ("content-type", v.as_str())).collect();
+        presigned(req, expire, &required)
+    }
+
+    /// Signs a `HEAD` request for the metadata of `path`.
+    pub fn presign_stat(&self, path: &str, expires_secs: f64) -> Result<Robj> {
+        let expire = presign_expiry(expires_secs)?;
+        let op = Operator::from(self.op.clone());
+        let req = runtime().block_on(op.presign_stat(path, expire))?;
+        presigned(req, expire, &[])
+    }
+
+    /// Signs a request that deletes `path`. None of the bundled services
+    /// can presign deletes yet, so this fails as unsupported for now.
+    pub fn presign_delete(&self, path: &str, expires_secs: f64) -> Result<Robj> {
+        let expire = presign_expiry(expires_secs)?;
+        let op = Operator::from(self.op.clone());
+        let req = runtime().block_on(op.presign_delete(path, expire))?;
+        presigned(req, expire, &[])
+    }
+}
+
+impl OpenDALOperator {
+    fn open_lister(
+        &self,
+        path: &str,
+        recursive: bool,
+        limit: Option<f64>,
+        start_after: Option<String>,
+        versions: bool,
+    ) -> Result<OpenDALLister> {
+        let limit = limit.map(|n| whole_u64(n, "limit")).transpose()?;
+        let info = self.op.info();
+        if versions && !info.full_capability().list_with_versions {
+            anyhow::bail!("the `{}` service doesn't list versions", info.scheme());
+        }
+
+        // The blocking lister has no `versions`, so use the async one.
+        let op = Operator::from(self.op.clone());
+        let mut list = op.lister_with(path).recursive(recursive).versions(versions);
+        if let Some(n) = limit {
+            list = list.limit(n as usize);
+        }
+        if let Some(after) = &start_after {
+            list = list.start_after(after);
+        }
+        let lister = runtime().block_on(async { list.await })?;
+
+        Ok(OpenDALLister {
+            lister,
+            peeked: None,
+            start_after,
+            remaining: limit,
+        })
+    }
+
+    /// Streams every file below `base` to `visit` with its size, stat'ing
+    /// files on services whose listings carry no sizes.
+    fn walk_files(&self, base: &str, mut visit: impl FnMut(String, u64)) -> Result<()> {
+        let listed_sizes = self.op.info().full_capability().list_has_content_length;
+        for entry in self.op.lister_with(base).recursive(true).call()? {
+            let (path, meta) = entry?.into_parts();
+            if !meta.is_file() {
+                continue;
+            }
+            let size = if listed_sizes {
+                meta.content_length()
+            } else {
+                self.op.stat(&path)?.content_length()
+            };
+            visit(path, size);
+        }
+        Ok(())
+    }
+
+    fn check_overwrite(&self, path: &str, overwrite: bool) -> Result<()> {
+        if !overwrite && self.op.exists(path)? {
+            anyhow::bail!("`{path}` already exists; use `overwrite = TRUE` to replace it");
+        }
+        Ok(())
+    }
+
+    /// The range size for a read: its own `chunk_size`, else the operator's
+    /// default. Parallel reads need ranges, so they fall back to 8 MiB.
+    fn read_chunk(&self, chunk_size: Option<f64>, concurrency: usize) -> Result<Option<usize>> {
+        let chunk = match chunk_size {
+            Some(n) => Some(positive_u32(n, "chunk_size")? as usize),
+            None => self.sizes.read_buffer,
+        };
+        Ok(chunk.or((concurrency > 1).then_some(transfer::CHUNK_SIZE)))
+    }
+
+    /// The part size for an upload: its own `chunk_size`, else the
+    /// operator's default.
+    fn write_chunk(&self, chunk_size: Option<f64>) -> Result<Option<usize>> {
+        match chunk_size {
+            Some(n) => Ok(Some(positive_u32(n, "chunk_size")? as usize)),
+            None => Ok(self.sizes.write_chunk),
+        }
+    }
+
+    fn check_append(&self) -> Result<()> {
+        let info = self.op.info();
+        if !info.full_capability().write_can_append {
+            anyhow::bail!("the `{}` service doesn't support appending", info.scheme());
+        }
+        Ok(())
+    }
+
+    /// Wraps this operator in `layer`, sharing the underlying service.
+    fn layer<L: Layer<Accessor>>(&self, layer: L) -> Result<Self> {
+        // Already blocking and measured, so skip `into_blocking`.
+        let operator = Operator::from(self.op.clone()).layer(layer);
+        Ok(Self {
+            op: operator.blocking(),
+            sizes: self.sizes,
+        })
+    }
 }
 
 // Macro to generate R exports
 extendr_module! {
     mod opendalr;
+    impl OpenDALLister;
     impl OpenDALMetadata;
     impl OpenDALOperator;
     impl OpenDALOperatorInfo;
+    impl OpenDALReader;
+    impl OpenDALWriter;
 }
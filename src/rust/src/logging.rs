@@ -0,0 +1,124 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+
+use extendr_api::prelude::*;
+use opendal::layers::LoggingInterceptor;
+use opendal::raw::{AccessorInfo, Operation};
+use opendal::ErrorKind;
+
+/// Severity of a storage log record, lowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum LogLevel {
+    /// Every operation as it starts.
+    Debug,
+    /// Completed operations.
+    Info,
+    /// Failed operations, including expected failures such as not found.
+    Warn,
+    /// Unexpected failures only.
+    Error,
+}
+
+impl LogLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" => Ok(LogLevel::Warn),
+            "error" => Ok(LogLevel::Error),
+            other => anyhow::bail!(
+                "unknown log level `{other}`; expected one of debug, info, warn, error"
+            ),
+        }
+    }
+}
+
+/// Forwards opendal's log records to `opendalr:::log_record()` in R.
+///
+/// Operators are driven through blocking calls from the R thread, so
+/// records normally arrive there and can be handed to R directly. Records
+/// logged from another thread, such as the part uploads and ranged reads
+/// opendal runs as tokio tasks, cannot touch the R API. They are queued and
+/// handed to R, in order, ahead of the next record logged on the R thread;
+/// every operation logs its outcome there, so they surface before the
+/// call that caused them returns.
+#[derive(Debug, Clone)]
+pub(crate) struct RLogger {
+    level: LogLevel,
+    thread: ThreadId,
+    pending: Arc<Mutex<Vec<(LogLevel, String)>>>,
+}
+
+impl RLogger {
+    /// Must be created on the R thread.
+    pub(crate) fn new(level: LogLevel) -> Self {
+        RLogger {
+            level,
+            thread: thread::current().id(),
+            pending: Arc::default(),
+        }
+    }
+
+    /// Hands queued records to R. Only call this on the R thread.
+    fn drain(&self) {
+        let pending = match self.pending.lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(_) => return,
+        };
+        for (level, line) in pending {
+            let _ = call!("opendalr:::log_record", level.as_str(), line);
+        }
+    }
+}
+
+impl LoggingInterceptor for RLogger {
+    fn log(
+        &self,
+        info: &AccessorInfo,
+        operation: Operation,
+        context: &[(&str, &str)],
+        message: &str,
+        err: Option<&opendal::Error>,
+    ) {
+        let level = match err {
+            Some(err) if err.kind() == ErrorKind::Unexpected => LogLevel::Error,
+            Some(_) => LogLevel::Warn,
+            None if message == "started" => LogLevel::Debug,
+            None => LogLevel::Info,
+        };
+        if level < self.level {
+            return;
+        }
+
+        let mut line = format!("{}:{} {operation}", info.scheme(), info.name());
+        for (key, value) in context {
+            let _ = write!(line, " {key}={value}");
+        }
+        let _ = write!(line, " {message}");
+        if let Some(err) = err {
+            let _ = write!(line, ": {err}");
+        }
+
+        if thread::current().id() == self.thread {
+            self.drain();
+            let _ = call!("opendalr:::log_record", level.as_str(), line);
+        } else if let Ok(mut pending) = self.pending.lock() {
+            pending.push((level, line));
+        }
+    }
+}
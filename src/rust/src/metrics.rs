@@ -0,0 +1,98 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use opendal::layers::observe::{MetricLabels, MetricValue, MetricsIntercept};
+use opendal::{OperatorInfo, Scheme};
+
+/// Latencies kept per operation for percentiles; older samples are dropped.
+const MAX_SAMPLES: usize = 10_000;
+
+type Key = (Scheme, Arc<str>, Arc<str>, &'static str);
+
+/// Totals for one operation on one service, accumulated for the session.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct OperationStats {
+    pub operation: &'static str,
+    pub count: u64,
+    pub errors: u64,
+    pub bytes: u64,
+    durations: VecDeque<f64>,
+}
+
+impl OperationStats {
+    /// Nearest-rank percentile of the recorded latencies, in seconds.
+    pub(crate) fn latency(&self, p: f64) -> Option<f64> {
+        if self.durations.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<f64> = self.durations.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let rank = ((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+        Some(sorted[rank - 1])
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<Key, OperationStats>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<Key, OperationStats>>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Records opendal's operation metrics into a process-wide registry.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SessionMetrics;
+
+impl MetricsIntercept for SessionMetrics {
+    fn observe(&self, labels: MetricLabels, value: MetricValue) {
+        let key = (labels.scheme, labels.namespace, labels.root, labels.operation);
+        let Ok(mut registry) = registry().lock() else {
+            return;
+        };
+        let stats = registry.entry(key).or_insert_with(|| OperationStats {
+            operation: labels.operation,
+            ..Default::default()
+        });
+
+        match value {
+            MetricValue::OperationDurationSeconds(d) => {
+                stats.count += 1;
+                if stats.durations.len() == MAX_SAMPLES {
+                    stats.durations.pop_front();
+                }
+                stats.durations.push_back(d.as_secs_f64());
+            }
+            MetricValue::OperationErrorsTotal => {
+                stats.count += 1;
+                stats.errors += 1;
+            }
+            MetricValue::OperationBytes(n) => stats.bytes += n,
+            _ => {}
+        }
+    }
+}
+
+/// Stats recorded for the service behind `info`, one per operation.
+///
+/// Metrics are labelled with the root of the operator they were attached
+/// to, so operators derived with a deeper root report their parent's.
+pub(crate) fn snapshot(info: &OperatorInfo) -> Vec<OperationStats> {
+    let Ok(registry) = registry().lock() else {
+        return Vec::new();
+    };
+
+    let mut merged: BTreeMap<&'static str, OperationStats> = BTreeMap::new();
+    let matching = registry.iter().filter(|((scheme, name, root, _), _)| {
+        *scheme == info.scheme() && **name == *info.name() && info.root().starts_with(&**root)
+    });
+    for (_, stats) in matching {
+        let total = merged.entry(stats.operation).or_insert_with(|| OperationStats {
+            operation: stats.operation,
+            ..Default::default()
+        });
+        total.count += stats.count;
+        total.errors += stats.errors;
+        total.bytes += stats.bytes;
+        total.durations.extend(stats.durations.iter().copied());
+    }
+
+    merged.into_values().collect()
+}
@@ -0,0 +1,50 @@
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use opentelemetry::global;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// Installs a global tracer provider that batches spans to the OTLP/HTTP
+/// collector at `endpoint`, e.g. `http://localhost:4318`. Only the first
+/// call takes effect; later calls keep the existing exporter.
+pub(crate) fn init(endpoint: &str, service_name: &str) -> Result<()> {
+    if PROVIDER.get().is_some() {
+        return Ok(());
+    }
+
+    let endpoint = endpoint.trim_end_matches('/');
+    let endpoint = if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{endpoint}/v1/traces")
+    };
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()?;
+
+    let resource = Resource::builder()
+        .with_service_name(service_name.to_string())
+        .build();
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build();
+
+    if PROVIDER.set(provider.clone()).is_ok() {
+        global::set_tracer_provider(provider);
+    }
+    Ok(())
+}
+
+/// Exports any spans still waiting in the batch.
+pub(crate) fn flush() -> Result<()> {
+    if let Some(provider) = PROVIDER.get() {
+        provider.force_flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use opendal::raw::*;
+use opendal::Result;
+
+/// Scopes an operator to a directory below its root by prefixing every path
+/// on the way in and stripping the prefix from listed entries on the way
+/// out. The wrapped accessor, and so its client and credentials, is shared.
+pub(crate) struct PrefixLayer {
+    prefix: String,
+}
+
+impl PrefixLayer {
+    pub(crate) fn new(prefix: &str) -> Self {
+        let mut prefix = normalize_path(prefix);
+        if prefix == "/" {
+            prefix.clear();
+        } else if !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+
+        PrefixLayer { prefix }
+    }
+}
+
+impl<A: Access> Layer<A> for PrefixLayer {
+    type LayeredAccess = PrefixAccessor<A>;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        // The inner info is shared with the parent operator, so report the
+        // new root through a copy rather than mutating it.
+        let parent = inner.info();
+        let info = AccessorInfo::default();
+        info.set_scheme(parent.scheme())
+            .set_name(&parent.name())
+            .set_root(&format!("{}{}", parent.root(), self.prefix))
+            .set_native_capability(parent.native_capability());
+        info.update_full_capability(|_| parent.full_capability());
+
+        PrefixAccessor {
+            inner,
+            info: Arc::new(info),
+            prefix: Arc::from(self.prefix.as_str()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct PrefixAccessor<A: Access> {
+    inner: A,
+    info: Arc<AccessorInfo>,
+    prefix: Arc<str>,
+}
+
+impl<A: Access> PrefixAccessor<A> {
+    fn join(&self, path: &str) -> String {
+        join(&self.prefix, path)
+    }
+}
+
+fn join(prefix: &str, path: &str) -> String {
+    match path {
+        "" | "/" => prefix.to_string(),
+        path => format!("{prefix}{path}"),
+    }
+}
+
+impl<A: Access> LayeredAccess for PrefixAccessor<A> {
+    type Inner = A;
+    type Reader = A::Reader;
+    type BlockingReader = A::BlockingReader;
+    type Writer = A::Writer;
+    type BlockingWriter = A::BlockingWriter;
+    type Lister = PrefixWrapper<A::Lister>;
+    type BlockingLister = PrefixWrapper<A::BlockingLister>;
+    type Deleter = PrefixWrapper<A::Deleter>;
+    type BlockingDeleter = PrefixWrapper<A::BlockingDeleter>;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    fn info(&self) -> Arc<AccessorInfo> {
+        self.info.clone()
+    }
+
+    async fn create_dir(&self, path: &str, args: OpCreateDir) -> Result<RpCreateDir> {
+        self.inner.create_dir(&self.join(path), args).await
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        self.inner.read(&self.join(path), args).await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        self.inner.write(&self.join(path), args).await
+    }
+
+    async fn copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        self.inner
+            .copy(&self.join(from), &self.join(to), args)
+            .await
+    }
+
+    async fn rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        self.inner
+            .rename(&self.join(from), &self.join(to), args)
+            .await
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        self.inner.stat(&self.join(path), args).await
+    }
+
+    async fn delete(&self) -> Result<(RpDelete, Self::Deleter)> {
+        let (rp, deleter) = self.inner.delete().await?;
+        Ok((rp, PrefixWrapper::new(self.prefix.clone(), deleter)))
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Lister)> {
+        let (rp, lister) = self.inner.list(&self.join(path), args).await?;
+        Ok((rp, PrefixWrapper::new(self.prefix.clone(), lister)))
+    }
+
+    async fn presign(&self, path: &str, args: OpPresign) -> Result<RpPresign> {
+        self.inner.presign(&self.join(path), args).await
+    }
+
+    fn blocking_create_dir(&self, path: &str, args: OpCreateDir) -> Result<RpCreateDir> {
+        self.inner.blocking_create_dir(&self.join(path), args)
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        self.inner.blocking_read(&self.join(path), args)
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        self.inner.blocking_write(&self.join(path), args)
+    }
+
+    fn blocking_copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        self.inner
+            .blocking_copy(&self.join(from), &self.join(to), args)
+    }
+
+    fn blocking_rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        self.inner
+            .blocking_rename(&self.join(from), &self.join(to), args)
+    }
+
+    fn blocking_stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        self.inner.blocking_stat(&self.join(path), args)
+    }
+
+    fn blocking_delete(&self) -> Result<(RpDelete, Self::BlockingDeleter)> {
+        let (rp, deleter) = self.inner.blocking_delete()?;
+        Ok((rp, PrefixWrapper::new(self.prefix.clone(), deleter)))
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingLister)> {
+        let (rp, lister) = self.inner.blocking_list(&self.join(path), args)?;
+        Ok((rp, PrefixWrapper::new(self.prefix.clone(), lister)))
+    }
+}
+
+/// Rewrites paths for listers and deleters of a [`PrefixAccessor`].
+pub(crate) struct PrefixWrapper<T> {
+    prefix: Arc<str>,
+    inner: T,
+}
+
+impl<T> PrefixWrapper<T> {
+    fn new(prefix: Arc<str>, inner: T) -> Self {
+        PrefixWrapper { prefix, inner }
+    }
+
+    fn strip(&self, mut entry: oio::Entry) -> oio::Entry {
+        if let Some(path) = entry.path().strip_prefix(&*self.prefix) {
+            let path = if path.is_empty() { "/" } else { path };
+            let path = path.to_string();
+            entry.set_path(&path);
+        }
+        entry
+    }
+}
+
+impl<T: oio::List> oio::List for PrefixWrapper<T> {
+    async fn next(&mut self) -> Result<Option<oio::Entry>> {
+        Ok(self.inner.next().await?.map(|entry| self.strip(entry)))
+    }
+}
+
+impl<T: oio::BlockingList> oio::BlockingList for PrefixWrapper<T> {
+    fn next(&mut self) -> Result<Option<oio::Entry>> {
+        Ok(self.inner.next()?.map(|entry| self.strip(entry)))
+    }
+}
+
+impl<T: oio::Delete> oio::Delete for PrefixWrapper<T> {
+    fn delete(&mut self, path: &str, args: OpDelete) -> Result<()> {
+        self.inner.delete(&join(&self.prefix, path), args)
+    }
+
+    async fn flush(&mut self) -> Result<usize> {
+        self.inner.flush().await
+    }
+}
+
+impl<T: oio::BlockingDelete> oio::BlockingDelete for PrefixWrapper<T> {
+    fn delete(&mut self, path: &str, args: OpDelete) -> Result<()> {
+        self.inner.delete(&join(&self.prefix, path), args)
+    }
+
+    fn flush(&mut self) -> Result<usize> {
+        self.inner.flush()
+    }
+}
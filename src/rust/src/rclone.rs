@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+use aes::cipher::{KeyIvInit, StreamCipher};
+use anyhow::Result;
+use base64::prelude::*;
+use ini::Ini;
+use opendal::Scheme;
+
+use crate::uri::split_bucket;
+
+/// The fixed key rclone uses to obscure passwords in its config file.
+const OBSCURE_KEY: [u8; 32] = [
+    0x9c, 0x93, 0x5b, 0x48, 0x73, 0x0a, 0x55, 0x4d, 0x6b, 0xfd, 0x7c, 0x63, 0xc8, 0x86, 0xa9, 0x2b,
+    0xd3, 0x90, 0x19, 0x8e, 0xb8, 0x12, 0x8a, 0xfb, 0xf4, 0xde, 0x16, 0x2b, 0x8b, 0x95, 0xf6, 0x38,
+];
+
+type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
+
+/// Reads the `remote` section of an rclone config file and translates it
+/// into a scheme and the options accepted by `Operator::via_iter`.
+///
+/// `path` is the part after `remote:`; for bucket based remotes its first
+/// segment names the bucket. Without `config_path` the file is found the
+/// way rclone finds it: `RCLONE_CONFIG`, then `~/.config/rclone/rclone.conf`.
+pub(crate) fn load_remote(
+    config_path: Option<&str>,
+    remote: &str,
+    path: &str,
+) -> Result<(Scheme, Vec<(String, String)>)> {
+    let config_path = match config_path {
+        Some(path) => PathBuf::from(path),
+        None => default_config_path()?,
+    };
+    let config = Ini::load_from_file(&config_path)?;
+
+    let Some(section) = config.section(Some(remote)) else {
+        anyhow::bail!(
+            "rclone remote `{remote}` was not found in {}",
+            config_path.display()
+        );
+    };
+    let section: HashMap<&str, &str> = section.iter().collect();
+    let get = |key: &str| section.get(key).copied().filter(|v| !v.is_empty());
+
+    let Some(kind) = get("type") else {
+        anyhow::bail!("rclone remote `{remote}` has no `type`");
+    };
+
+    let mut options = Vec::new();
+    let mut set = |key: &str, value: Option<String>| {
+        if let Some(value) = value {
+            options.push((key.to_string(), value));
+        }
+    };
+
+    let scheme = match kind {
+        "local" => {
+            let root = if path.is_empty() { "." } else { path };
+            set("root", Some(root.to_string()));
+            return Ok((Scheme::Fs, options));
+        }
+        "memory" => Scheme::Memory,
+        "s3" => {
+            set("access_key_id", get("access_key_id").map(str::to_string));
+            set(
+                "secret_access_key",
+                get("secret_access_key").map(str::to_string),
+            );
+            set("session_token", get("session_token").map(str::to_string));
+            set("region", get("region").map(str::to_string));
+            set("endpoint", get("endpoint").map(str::to_string));
+            if get("force_path_style") == Some("false") {
+                set("enable_virtual_host_style", Some("true".to_string()));
+            }
+            return bucket_options(Scheme::S3, remote, path, options);
+        }
+        "google cloud storage" => {
+            set(
+                "credential_path",
+                get("service_account_file").map(str::to_string),
+            );
+            set(
+                "credential",
+                get("service_account_credentials").map(|json| BASE64_STANDARD.encode(json)),
+            );
+            return bucket_options(Scheme::Gcs, remote, path, options);
+        }
+        "webdav" => {
+            set("endpoint", get("url").map(str::to_string));
+            set("username", get("user").map(str::to_string));
+            set("password", get("pass").map(reveal).transpose()?);
+            set("token", get("bearer_token").map(str::to_string));
+            Scheme::Webdav
+        }
+        "http" => {
+            set("endpoint", get("url").map(str::to_string));
+            Scheme::Http
+        }
+        "ftp" => {
+            let tls = get("tls") == Some("true");
+            let host = get("host").unwrap_or_default();
+            let port = get("port").unwrap_or("21");
+            let endpoint_scheme = if tls { "ftps" } else { "ftp" };
+            set(
+                "endpoint",
+                Some(format!("{endpoint_scheme}://{host}:{port}")),
+            );
+            set("user", get("user").map(str::to_string));
+            set("password", get("pass").map(reveal).transpose()?);
+            Scheme::Ftp
+        }
+        "sftp" => {
+            if get("pass").is_some() || get("key_file_pass").is_some() {
+                anyhow::bail!(
+                    "rclone remote `{remote}` uses a password; opendal's sftp service \
+                     only supports keys, so add the key to ssh-agent instead"
+                );
+            }
+            let host = get("host").unwrap_or_default();
+            let port = get("port").unwrap_or("22");
+            set("endpoint", Some(format!("ssh://{host}:{port}")));
+            set("user", get("user").map(str::to_string));
+            set("key", get("key_file").map(str::to_string));
+            Scheme::Sftp
+        }
+        "hdfs" => {
+            set(
+                "name_node",
+                get("namenode").map(|nn| format!("hdfs://{nn}")),
+            );
+            set("user", get("username").map(str::to_string));
+            Scheme::Hdfs
+        }
+        "dropbox" | "drive" => {
+            let token = get("token").map(parse_token).transpose()?;
+            if let Some((access_token, refresh_token)) = token {
+                set("access_token", access_token);
+                set("refresh_token", refresh_token);
+            }
+            set("client_id", get("client_id").map(str::to_string));
+            set("client_secret", get("client_secret").map(str::to_string));
+            if kind == "dropbox" {
+                Scheme::Dropbox
+            } else {
+                Scheme::Gdrive
+            }
+        }
+        "yandex" => {
+            let token = get("token").map(parse_token).transpose()?;
+            set(
+                "access_token",
+                token.and_then(|(access_token, _)| access_token),
+            );
+            Scheme::YandexDisk
+        }
+        "koofr" => {
+            set(
+                "endpoint",
+                get("endpoint")
+                    .or(Some("https://app.koofr.net"))
+                    .map(str::to_string),
+            );
+            set("email", get("user").map(str::to_string));
+            set("password", get("password").map(reveal).transpose()?);
+            Scheme::Koofr
+        }
+        "seafile" => {
+            set("endpoint", get("url").map(str::to_string));
+            set("username", get("user").map(str::to_string));
+            set("password", get("pass").map(reveal).transpose()?);
+            set("repo_name", get("library").map(str::to_string));
+            Scheme::Seafile
+        }
+        other => anyhow::bail!("rclone remote type `{other}` is not supported"),
+    };
+
+    options.push(("root".to_string(), path.to_string()));
+    Ok((scheme, options))
+}
+
+/// Splits `bucket/prefix` into the bucket and root options.
+fn bucket_options(
+    scheme: Scheme,
+    remote: &str,
+    path: &str,
+    mut options: Vec<(String, String)>,
+) -> Result<(Scheme, Vec<(String, String)>)> {
+    let Some((bucket, root)) = split_bucket(path) else {
+        anyhow::bail!("`root` must start with a bucket name for rclone remote `{remote}`");
+    };
+
+    options.push(("bucket".to_string(), bucket));
+    options.push(("root".to_string(), root));
+    Ok((scheme, options))
+}
+
+fn default_config_path() -> Result<PathBuf> {
+    if let Some(path) = env::var_os("RCLONE_CONFIG") {
+        return Ok(PathBuf::from(path));
+    }
+
+    if cfg!(windows) {
+        if let Some(appdata) = env::var_os("APPDATA") {
+            return Ok(PathBuf::from(appdata).join("rclone").join("rclone.conf"));
+        }
+    }
+
+    match home::home_dir() {
+        Some(home) => Ok(home.join(".config").join("rclone").join("rclone.conf")),
+        None => anyhow::bail!("could not locate rclone.conf; pass `config_path` explicitly"),
+    }
+}
+
+/// Reverses `rclone obscure`: AES-256-CTR under a fixed key, with the IV
+/// prepended and the result base64url encoded.
+fn reveal(obscured: &str) -> Result<String> {
+    let mut bytes = BASE64_URL_SAFE_NO_PAD.decode(obscured.trim_end_matches('='))?;
+    if bytes.len() < 16 {
+        anyhow::bail!("rclone password is too short to be obscured");
+    }
+
+    let (iv, text) = bytes.split_at_mut(16);
+    let mut cipher = Aes256Ctr::new(&OBSCURE_KEY.into(), (&*iv).into());
+    cipher.apply_keystream(text);
+
+    Ok(String::from_utf8(text.to_vec())?)
+}
+
+/// Pulls the access and refresh tokens out of rclone's OAuth token JSON.
+fn parse_token(token: &str) -> Result<(Option<String>, Option<String>)> {
+    let token: serde_json::Value = serde_json::from_str(token)?;
+    let field = |name: &str| token.get(name).and_then(|v| v.as_str()).map(str::to_string);
+
+    Ok((field("access_token"), field("refresh_token")))
+}
@@ -0,0 +1,378 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use opendal::raw::*;
+use opendal::{Buffer, Error, ErrorKind, Metadata, OperatorInfo, Result};
+
+/// What a stat last returned, `None` meaning the path didn't exist.
+struct Entry {
+    meta: Option<Metadata>,
+    checked: Instant,
+}
+
+/// Entries for every cached operator in the session, keyed by service and
+/// absolute path so that operators sharing a service, including ones
+/// derived with `with_root()`, see and invalidate the same entries.
+fn entries() -> &'static Mutex<BTreeMap<String, Entry>> {
+    static ENTRIES: OnceLock<Mutex<BTreeMap<String, Entry>>> = OnceLock::new();
+    ENTRIES.get_or_init(Default::default)
+}
+
+fn namespace(scheme: impl std::fmt::Display, name: &str, root: &str) -> String {
+    format!("{scheme}\n{name}\n{root}")
+}
+
+/// Drops the cached stats of every path below `prefix` on the service behind
+/// `info`.
+pub(crate) fn invalidate(info: &OperatorInfo, prefix: &str) {
+    let Ok(mut entries) = entries().lock() else {
+        return;
+    };
+    let start = format!(
+        "{}{}",
+        namespace(info.scheme(), &info.name(), &info.root()),
+        prefix.trim_start_matches('/')
+    );
+
+    let stale: Vec<String> = entries
+        .range(start.clone()..)
+        .map(|(key, _)| key)
+        .take_while(|key| key.starts_with(&start))
+        .cloned()
+        .collect();
+    for key in &stale {
+        entries.remove(key);
+    }
+}
+
+/// Remembers what `stat()`, and so `exists()`, found for each path,
+/// including that it was missing, and answers repeated stats from memory
+/// for `ttl` or, without one, until the path is invalidated.
+///
+/// Writes, copies, renames, deletes and new directories made through the
+/// cached operator drop the entries they affect, along with those of the
+/// parent directories. Changes made any other way are only noticed once the
+/// entries expire or are invalidated. Stats of a specific version, or with
+/// conditions or overrides, go straight to the service.
+pub(crate) struct StatCacheLayer {
+    ttl: Option<Duration>,
+}
+
+impl StatCacheLayer {
+    pub(crate) fn new(ttl: Option<Duration>) -> Self {
+        StatCacheLayer { ttl }
+    }
+}
+
+impl<A: Access> Layer<A> for StatCacheLayer {
+    type LayeredAccess = StatCacheAccessor<A>;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        let info = inner.info();
+        let cache = StatCache {
+            namespace: namespace(info.scheme(), &info.name(), &info.root()),
+            ttl: self.ttl,
+        };
+
+        StatCacheAccessor {
+            inner,
+            cache: Arc::new(cache),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct StatCache {
+    namespace: String,
+    ttl: Option<Duration>,
+}
+
+impl StatCache {
+    fn key(&self, path: &str) -> String {
+        format!("{}{}", self.namespace, path.trim_start_matches('/'))
+    }
+
+    /// The cached outcome of statting `path`, if there is a current one.
+    fn get(&self, path: &str) -> Option<Result<RpStat>> {
+        let entries = entries().lock().ok()?;
+        let entry = entries.get(&self.key(path))?;
+        if self.ttl.is_some_and(|ttl| entry.checked.elapsed() >= ttl) {
+            return None;
+        }
+
+        Some(match &entry.meta {
+            Some(meta) => Ok(RpStat::new(meta.clone())),
+            None => Err(Error::new(
+                ErrorKind::NotFound,
+                "path was not found when last checked",
+            )
+            .with_operation("stat")
+            .with_context("path", path)),
+        })
+    }
+
+    /// Records the outcome of statting `path`. Other failures, such as
+    /// being denied, are not remembered.
+    fn put(&self, path: &str, result: &Result<RpStat>) {
+        let meta = match result {
+            Ok(rp) => Some(rp.clone().into_metadata()),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(_) => return,
+        };
+        if let Ok(mut entries) = entries().lock() {
+            let entry = Entry {
+                meta,
+                checked: Instant::now(),
+            };
+            entries.insert(self.key(path), entry);
+        }
+    }
+
+    /// Drops the entries of `path` and the directories above it, whose
+    /// existence may have changed with it.
+    fn forget(&self, path: &str) {
+        let path = path.trim_start_matches('/');
+        let Ok(mut entries) = entries().lock() else {
+            return;
+        };
+        entries.remove(&self.key(path));
+        entries.remove(&self.key(""));
+        for (i, _) in path.trim_end_matches('/').match_indices('/') {
+            entries.remove(&self.key(&path[..=i]));
+        }
+    }
+}
+
+/// Stats that must not be answered from the cache.
+fn bypass(args: &OpStat) -> bool {
+    args.version().is_some()
+        || args.if_match().is_some()
+        || args.if_none_match().is_some()
+        || args.if_modified_since().is_some()
+        || args.if_unmodified_since().is_some()
+        || args.override_content_type().is_some()
+        || args.override_cache_control().is_some()
+        || args.override_content_disposition().is_some()
+}
+
+#[derive(Debug)]
+pub(crate) struct StatCacheAccessor<A: Access> {
+    inner: A,
+    cache: Arc<StatCache>,
+}
+
+impl<A: Access> LayeredAccess for StatCacheAccessor<A> {
+    type Inner = A;
+    type Reader = A::Reader;
+    type BlockingReader = A::BlockingReader;
+    type Writer = StatCacheWriter<A::Writer>;
+    type BlockingWriter = StatCacheWriter<A::BlockingWriter>;
+    type Lister = A::Lister;
+    type BlockingLister = A::BlockingLister;
+    type Deleter = StatCacheDeleter<A::Deleter>;
+    type BlockingDeleter = StatCacheDeleter<A::BlockingDeleter>;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn create_dir(&self, path: &str, args: OpCreateDir) -> Result<RpCreateDir> {
+        self.cache.forget(path);
+        self.inner.create_dir(path, args).await
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        if bypass(&args) {
+            return self.inner.stat(path, args).await;
+        }
+        if let Some(cached) = self.cache.get(path) {
+            return cached;
+        }
+        let result = self.inner.stat(path, args).await;
+        self.cache.put(path, &result);
+        result
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        self.inner.read(path, args).await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        self.cache.forget(path);
+        let (rp, writer) = self.inner.write(path, args).await?;
+        Ok((rp, StatCacheWriter::new(self.cache.clone(), path, writer)))
+    }
+
+    async fn copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        let copied = self.inner.copy(from, to, args).await;
+        self.cache.forget(to);
+        copied
+    }
+
+    async fn rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        let renamed = self.inner.rename(from, to, args).await;
+        self.cache.forget(from);
+        self.cache.forget(to);
+        renamed
+    }
+
+    async fn delete(&self) -> Result<(RpDelete, Self::Deleter)> {
+        let (rp, deleter) = self.inner.delete().await?;
+        Ok((rp, StatCacheDeleter::new(self.cache.clone(), deleter)))
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Lister)> {
+        self.inner.list(path, args).await
+    }
+
+    fn blocking_create_dir(&self, path: &str, args: OpCreateDir) -> Result<RpCreateDir> {
+        self.cache.forget(path);
+        self.inner.blocking_create_dir(path, args)
+    }
+
+    fn blocking_stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        if bypass(&args) {
+            return self.inner.blocking_stat(path, args);
+        }
+        if let Some(cached) = self.cache.get(path) {
+            return cached;
+        }
+        let result = self.inner.blocking_stat(path, args);
+        self.cache.put(path, &result);
+        result
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        self.inner.blocking_read(path, args)
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        self.cache.forget(path);
+        let (rp, writer) = self.inner.blocking_write(path, args)?;
+        Ok((rp, StatCacheWriter::new(self.cache.clone(), path, writer)))
+    }
+
+    fn blocking_copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        let copied = self.inner.blocking_copy(from, to, args);
+        self.cache.forget(to);
+        copied
+    }
+
+    fn blocking_rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        let renamed = self.inner.blocking_rename(from, to, args);
+        self.cache.forget(from);
+        self.cache.forget(to);
+        renamed
+    }
+
+    fn blocking_delete(&self) -> Result<(RpDelete, Self::BlockingDeleter)> {
+        let (rp, deleter) = self.inner.blocking_delete()?;
+        Ok((rp, StatCacheDeleter::new(self.cache.clone(), deleter)))
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingLister)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+/// Drops the written path's entries again once the object is committed, in
+/// case it was stat'ed while the upload was in progress.
+pub(crate) struct StatCacheWriter<W> {
+    cache: Arc<StatCache>,
+    path: String,
+    inner: W,
+}
+
+impl<W> StatCacheWriter<W> {
+    fn new(cache: Arc<StatCache>, path: &str, inner: W) -> Self {
+        StatCacheWriter {
+            cache,
+            path: path.to_string(),
+            inner,
+        }
+    }
+}
+
+impl<W: oio::Write> oio::Write for StatCacheWriter<W> {
+    async fn write(&mut self, bs: Buffer) -> Result<()> {
+        self.inner.write(bs).await
+    }
+
+    async fn close(&mut self) -> Result<Metadata> {
+        let closed = self.inner.close().await;
+        self.cache.forget(&self.path);
+        closed
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+}
+
+impl<W: oio::BlockingWrite> oio::BlockingWrite for StatCacheWriter<W> {
+    fn write(&mut self, bs: Buffer) -> Result<()> {
+        self.inner.write(bs)
+    }
+
+    fn close(&mut self) -> Result<Metadata> {
+        let closed = self.inner.close();
+        self.cache.forget(&self.path);
+        closed
+    }
+}
+
+/// Drops the entries of paths as they are queued for deletion, and again
+/// once the deletions are flushed.
+pub(crate) struct StatCacheDeleter<D> {
+    cache: Arc<StatCache>,
+    queued: Vec<String>,
+    inner: D,
+}
+
+impl<D> StatCacheDeleter<D> {
+    fn new(cache: Arc<StatCache>, inner: D) -> Self {
+        StatCacheDeleter {
+            cache,
+            queued: Vec::new(),
+            inner,
+        }
+    }
+
+    fn queue(&mut self, path: &str) {
+        self.cache.forget(path);
+        self.queued.push(path.to_string());
+    }
+
+    fn flushed(&mut self) {
+        for path in self.queued.drain(..) {
+            self.cache.forget(&path);
+        }
+    }
+}
+
+impl<D: oio::Delete> oio::Delete for StatCacheDeleter<D> {
+    fn delete(&mut self, path: &str, args: OpDelete) -> Result<()> {
+        self.queue(path);
+        self.inner.delete(path, args)
+    }
+
+    async fn flush(&mut self) -> Result<usize> {
+        let flushed = self.inner.flush().await;
+        self.flushed();
+        flushed
+    }
+}
+
+impl<D: oio::BlockingDelete> oio::BlockingDelete for StatCacheDeleter<D> {
+    fn delete(&mut self, path: &str, args: OpDelete) -> Result<()> {
+        self.queue(path);
+        self.inner.delete(path, args)
+    }
+
+    fn flush(&mut self) -> Result<usize> {
+        let flushed = self.inner.flush();
+        self.flushed();
+        flushed
+    }
+}
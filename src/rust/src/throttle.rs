@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use opendal::raw::*;
+use opendal::{Buffer, Metadata, Result};
+
+/// Splits every write into pieces of at most `max` bytes.
+///
+/// opendal's `ThrottleLayer` rejects any single write larger than its burst
+/// size, and a whole-object `write()` arrives as one buffer. Layered on top
+/// of it, this lets large uploads be paced instead of failing.
+pub(crate) struct SplitWritesLayer {
+    max: usize,
+}
+
+impl SplitWritesLayer {
+    pub(crate) fn new(max: usize) -> Self {
+        SplitWritesLayer { max: max.max(1) }
+    }
+}
+
+impl<A: Access> Layer<A> for SplitWritesLayer {
+    type LayeredAccess = SplitWritesAccessor<A>;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        SplitWritesAccessor {
+            inner,
+            max: self.max,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct SplitWritesAccessor<A: Access> {
+    inner: A,
+    max: usize,
+}
+
+impl<A: Access> LayeredAccess for SplitWritesAccessor<A> {
+    type Inner = A;
+    type Reader = A::Reader;
+    type BlockingReader = A::BlockingReader;
+    type Writer = SplitWriter<A::Writer>;
+    type BlockingWriter = SplitWriter<A::BlockingWriter>;
+    type Lister = A::Lister;
+    type BlockingLister = A::BlockingLister;
+    type Deleter = A::Deleter;
+    type BlockingDeleter = A::BlockingDeleter;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    fn info(&self) -> Arc<AccessorInfo> {
+        self.inner.info()
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        self.inner.read(path, args).await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        let (rp, writer) = self.inner.write(path, args).await?;
+        Ok((rp, SplitWriter::new(writer, self.max)))
+    }
+
+    async fn delete(&self) -> Result<(RpDelete, Self::Deleter)> {
+        self.inner.delete().await
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Lister)> {
+        self.inner.list(path, args).await
+    }
+
+    fn blocking_read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::BlockingReader)> {
+        self.inner.blocking_read(path, args)
+    }
+
+    fn blocking_write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::BlockingWriter)> {
+        let (rp, writer) = self.inner.blocking_write(path, args)?;
+        Ok((rp, SplitWriter::new(writer, self.max)))
+    }
+
+    fn blocking_delete(&self) -> Result<(RpDelete, Self::BlockingDeleter)> {
+        self.inner.blocking_delete()
+    }
+
+    fn blocking_list(&self, path: &str, args: OpList) -> Result<(RpList, Self::BlockingLister)> {
+        self.inner.blocking_list(path, args)
+    }
+}
+
+pub(crate) struct SplitWriter<W> {
+    inner: W,
+    max: usize,
+}
+
+impl<W> SplitWriter<W> {
+    fn new(inner: W, max: usize) -> Self {
+        SplitWriter { inner, max }
+    }
+
+    fn pieces(&self, bs: Buffer) -> impl Iterator<Item = Buffer> {
+        let max = self.max;
+        (0..bs.len())
+            .step_by(max)
+            .map(move |start| bs.slice(start..(start + max).min(bs.len())))
+    }
+}
+
+impl<W: oio::Write> oio::Write for SplitWriter<W> {
+    async fn write(&mut self, bs: Buffer) -> Result<()> {
+        for piece in self.pieces(bs) {
+            self.inner.write(piece).await?;
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<Metadata> {
+        self.inner.close().await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+}
+
+impl<W: oio::BlockingWrite> oio::BlockingWrite for SplitWriter<W> {
+    fn write(&mut self, bs: Buffer) -> Result<()> {
+        for piece in self.pieces(bs) {
+            self.inner.write(piece)?;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<Metadata> {
+        self.inner.close()
+    }
+}
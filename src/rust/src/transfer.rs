@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+use futures::StreamExt;
+use glob::{MatchOptions, Pattern};
+use md5::{Digest, Md5};
+use opendal::{Entry, Metadata, Operator};
+
+/// Chunk size for the files a directory transfer moves.
+pub(crate) const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Selects paths by `include` and `exclude` globs, matched against paths
+/// relative to the transfer's root: `*` stays within a directory and `**`
+/// crosses them. No `include` means everything not excluded.
+pub(crate) struct PathFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl PathFilter {
+    pub(crate) fn new(include: Option<Vec<String>>, exclude: Option<Vec<String>>) -> Result<Self> {
+        let compile = |globs: Option<Vec<String>>| -> Result<Vec<Pattern>> {
+            Ok(globs
+                .unwrap_or_default()
+                .iter()
+                .map(|g| Pattern::new(g))
+                .collect::<Result<_, _>>()?)
+        };
+        Ok(PathFilter {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    pub(crate) fn matches(&self, relative: &str) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        let matching = |p: &Pattern| p.matches_with(relative, options);
+        (self.include.is_empty() || self.include.iter().any(matching))
+            && !self.exclude.iter().any(matching)
+    }
+}
+
+/// Joins a path relative to a transfer's root onto a remote prefix.
+pub(crate) fn remote_path(prefix: &str, relative: &str) -> String {
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        relative.to_string()
+    } else {
+        format!("{prefix}/{relative}")
+    }
+}
+
+/// Where an object at `relative` below a remote prefix lands in `dir`.
+/// Keys that would climb out of `dir`, such as `../x`, are refused.
+pub(crate) fn local_path(dir: &Path, relative: &str) -> Result<PathBuf> {
+    let relative = Path::new(relative);
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        anyhow::bail!(
+            "refusing to download `{}` outside `{}`",
+            relative.display(),
+            dir.display()
+        );
+    }
+    Ok(dir.join(relative))
+}
+
+/// Whether the local file at `local` already holds the object `meta`
+/// describes: it must be the same size and, where the etag is a plain MD5
+/// digest (as for single-part S3 uploads), have the same content.
+pub(crate) fn up_to_date(local: &Path, meta: &Metadata) -> Result<bool> {
+    let Ok(local_meta) = std::fs::metadata(local) else {
+        return Ok(false);
+    };
+    if !local_meta.is_file() || local_meta.len() != meta.content_length() {
+        return Ok(false);
+    }
+
+    let etag = meta
+        .etag()
+        .map(|e| e.trim_start_matches("W/").trim_matches('"'));
+    let Some(digest) = etag.filter(|e| e.len() == 32 && e.chars().all(|c| c.is_ascii_hexdigit()))
+    else {
+        return Ok(true);
+    };
+    let mut hasher = Md5::new();
+    std::io::copy(&mut File::open(local)?, &mut hasher)?;
+    let local_digest: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    Ok(local_digest.eq_ignore_ascii_case(digest))
+}
+
+/// Every file below `dir`, following symlinks, as `/`-separated paths
+/// relative to it with their sizes, sorted by path.
+pub(crate) fn local_files(dir: &Path) -> Result<Vec<(String, PathBuf, u64)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = std::fs::read_dir(&current)
+            .map_err(|e| anyhow::anyhow!("can't read `{}`: {e}", current.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            let meta = std::fs::metadata(&path)?;
+            if meta.is_dir() {
+                pending.push(path);
+            } else if meta.is_file() {
+                let relative = path.strip_prefix(dir)?.components();
+                let relative: Vec<_> = relative.map(|c| c.as_os_str().to_string_lossy()).collect();
+                files.push((relative.join("/"), path, meta.len()));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Streams the local file at `local` to `remote`, `chunk_size` bytes at a
+/// time with up to `concurrency` parts in flight, returning the number of
+/// bytes sent. Services that can't take an upload in parts are sent the
+/// file in one request, which the writer assembles from the pieces read.
+/// A failed upload is aborted rather than left half written.
+pub(crate) async fn upload(
+    op: &Operator,
+    local: &Path,
+    remote: &str,
+    chunk_size: usize,
+    concurrency: usize,
+) -> Result<u64> {
+    let mut file =
+        File::open(local).map_err(|e| anyhow::anyhow!("can't open `{}`: {e}", local.display()))?;
+
+    // Without multipart writes, a single chunk spanning the file makes the
+    // writer hold the pieces back until close.
+    let part = if op.info().full_capability().write_can_multi {
+        chunk_size
+    } else {
+        usize::try_from(file.metadata()?.len())?.max(1)
+    };
+    let mut writer = op
+        .writer_with(remote)
+        .chunk(part)
+        .concurrent(concurrency)
+        .await?;
+    let sent = async {
+        let mut sent = 0;
+        loop {
+            let mut buf = Vec::with_capacity(chunk_size);
+            let n = (&mut file).take(chunk_size as u64).read_to_end(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            writer.write(buf).await?;
+            sent += n as u64;
+        }
+        writer.close().await?;
+        Ok(sent)
+    }
+    .await;
+    if sent.is_err() {
+        let _ = writer.abort().await;
+    }
+    sent
+}
+
+/// Streams `remote` to the local file at `local`, returning the number of
+/// bytes received. With a `chunk_size`, the object is fetched in ranges of
+/// that many bytes, `concurrency` at a time, and written in order.
+/// The download goes to `<local>.part` first, so a failure never leaves a
+/// truncated file behind.
+pub(crate) async fn download(
+    op: &Operator,
+    remote: &str,
+    local: &Path,
+    version: Option<&str>,
+    chunk_size: Option<usize>,
+    concurrency: usize,
+) -> Result<u64> {
+    let mut partial = local.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+    let mut file = File::create(&partial)
+        .map_err(|e| anyhow::anyhow!("can't create `{}`: {e}", partial.display()))?;
+
+    let mut reader = op.reader_with(remote);
+    if let Some(v) = version {
+        reader = reader.version(v);
+    }
+    if let Some(n) = chunk_size {
+        reader = reader.chunk(n).concurrent(concurrency);
+    }
+    let received = async {
+        let mut chunks = reader.await?.into_bytes_stream(..).await?;
+        let mut received = 0;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            file.write_all(&chunk)?;
+            received += chunk.len() as u64;
+        }
+        file.sync_all()?;
+        Ok::<_, anyhow::Error>(received)
+    }
+    .await;
+
+    drop(file);
+    match received {
+        Ok(_) => std::fs::rename(&partial, local)
+            .map_err(|e| anyhow::anyhow!("can't move download to `{}`: {e}", local.display()))?,
+        Err(_) => {
+            let _ = std::fs::remove_file(&partial);
+        }
+    }
+    received
+}
+
+/// Normalizes a remote prefix to list it as a directory: no leading `/`,
+/// and a trailing one unless it names the root.
+pub(crate) fn dir_prefix(prefix: &str) -> String {
+    match prefix.trim_start_matches('/') {
+        "" => String::new(),
+        p if p.ends_with('/') => p.to_string(),
+        p => format!("{p}/"),
+    }
+}
+
+/// Every object below the directory `base`, keyed by its path relative to
+/// `base`. Services whose listings carry no sizes are stat'ed per object,
+/// `concurrency` at a time.
+pub(crate) async fn remote_files(
+    op: &Operator,
+    base: &str,
+    concurrency: usize,
+) -> Result<BTreeMap<String, Metadata>> {
+    let listed_sizes = op.info().full_capability().list_has_content_length;
+    let files: Vec<(String, Metadata)> = op
+        .list_with(base)
+        .recursive(true)
+        .await?
+        .into_iter()
+        .map(Entry::into_parts)
+        .filter(|(_, meta)| meta.is_file())
+        .collect();
+
+    let files = futures::stream::iter(files)
+        .map(|(path, meta)| async move {
+            let meta = if listed_sizes {
+                meta
+            } else {
+                op.stat(&path).await?
+            };
+            let relative = path.strip_prefix(base).unwrap_or(&path).to_string();
+            Ok::<_, opendal::Error>((relative, meta))
+        })
+        .buffered(concurrency)
+        .collect::<Vec<_>>()
+        .await;
+    Ok(files.into_iter().collect::<opendal::Result<_>>()?)
+}
+
+/// Whether an object needs copying over its counterpart at the
+/// destination: they differ in size, in etag where the two come from the
+/// same kind of service and neither is a multipart etag, or else the
+/// source was modified after the destination.
+pub(crate) fn changed(src: &Metadata, dst: &Metadata, comparable_etags: bool) -> bool {
+    if src.content_length() != dst.content_length() {
+        return true;
+    }
+    let whole = |meta: &Metadata| meta.etag().filter(|e| !e.contains('-')).map(str::to_string);
+    if let (true, Some(a), Some(b)) = (comparable_etags, whole(src), whole(dst)) {
+        return a != b;
+    }
+    match (src.last_modified(), dst.last_modified()) {
+        (Some(a), Some(b)) => a > b,
+        _ => false,
+    }
+}
+
+/// Streams `src_path` on `src` to `dst_path` on `dst`, which may be
+/// different services, returning the number of bytes copied. A failed
+/// copy is aborted rather than left half written.
+pub(crate) async fn copy(
+    src: &Operator,
+    src_path: &str,
+    dst: &Operator,
+    dst_path: &str,
+) -> Result<u64> {
+    let mut chunks = src.reader(src_path).await?.into_bytes_stream(..).await?;
+    let mut writer = dst.writer(dst_path).await?;
+    let copied = async {
+        let mut copied = 0;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            copied += chunk.len() as u64;
+            writer.write(chunk).await?;
+        }
+        writer.close().await?;
+        Ok(copied)
+    }
+    .await;
+    if copied.is_err() {
+        let _ = writer.abort().await;
+    }
+    copied
+}
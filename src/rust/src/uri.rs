@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use opendal::Scheme;
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+/// Services whose URI host names the bucket, e.g. `s3://bucket/prefix`.
+const BUCKET_SCHEMES: &[Scheme] = &[Scheme::S3, Scheme::Gcs, Scheme::Cos, Scheme::Obs];
+
+/// Splits a connection URI into a scheme and the options accepted by
+/// `Operator::via_iter`.
+///
+/// The host maps to the service's bucket or endpoint, the path becomes the
+/// root and query parameters are passed through as-is, overriding both.
+pub(crate) fn parse_uri(uri: &str) -> Result<(Scheme, Vec<(String, String)>)> {
+    let url = Url::parse(uri)?;
+    let mut options = Vec::new();
+
+    let scheme = match url.scheme() {
+        "file" => Scheme::Fs,
+        "http" | "https" => Scheme::Http,
+        "abfs" | "abfss" => Scheme::Azdls,
+        s => Scheme::from_str(s)?,
+    };
+
+    let host = url.host_str().unwrap_or_default();
+    let authority = match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    };
+    let path = url.path();
+
+    match scheme {
+        Scheme::Fs | Scheme::Memory => {
+            // `file:///abs/path`, or `file://rel/path` for a relative root.
+            options.push(("root".to_string(), format!("{authority}{path}")));
+        }
+        Scheme::Azdls => {
+            // Hadoop style: `abfs://<filesystem>@<account>.dfs.core.windows.net/path`
+            options.push(("filesystem".to_string(), decode(url.username())));
+            options.push(("endpoint".to_string(), format!("https://{authority}")));
+            options.push(("root".to_string(), path.to_string()));
+        }
+        s if BUCKET_SCHEMES.contains(&s) => {
+            options.push(("bucket".to_string(), authority));
+            options.push(("root".to_string(), path.to_string()));
+        }
+        _ => {
+            if !authority.is_empty() {
+                let endpoint_scheme = match url.scheme() {
+                    "http" | "https" | "ftp" | "ftps" | "redis" | "rediss" => url.scheme(),
+                    "sftp" => "ssh",
+                    _ => "https",
+                };
+                let endpoint = format!("{endpoint_scheme}://{authority}");
+                options.push(("endpoint".to_string(), endpoint));
+            }
+            if !url.username().is_empty() {
+                let key = match scheme {
+                    Scheme::Ftp | Scheme::Sftp => "user",
+                    _ => "username",
+                };
+                options.push((key.to_string(), decode(url.username())));
+            }
+            if let Some(password) = url.password() {
+                options.push(("password".to_string(), decode(password)));
+            }
+            options.push(("root".to_string(), path.to_string()));
+        }
+    }
+
+    for (key, value) in url.query_pairs() {
+        options.retain(|(k, _)| k != key.as_ref());
+        options.push((key.into_owned(), value.into_owned()));
+    }
+
+    Ok((scheme, options))
+}
+
+/// Splits `bucket/prefix` into the bucket and a root under it, for services
+/// addressed by a path that starts with the bucket.
+pub(crate) fn split_bucket(path: &str) -> Option<(String, String)> {
+    let path = path.trim_start_matches('/');
+    let (bucket, root) = path.split_once('/').unwrap_or((path, ""));
+    if bucket.is_empty() {
+        return None;
+    }
+
+    Some((bucket.to_string(), format!("/{root}")))
+}
+
+fn decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().into_owned()
+}